@@ -1,6 +1,6 @@
 use std::fs::File;
 use std::io::Write;
-use trtc_rust::canvas::Canvas;
+use trtc_rust::canvas::{Canvas, Origin};
 use trtc_rust::color::Color;
 use trtc_rust::tuple::Tuple;
 
@@ -34,13 +34,13 @@ fn main() {
         wind: Tuple::new_point(-0.01, 0.0, 0.0),
     };
 
-    let mut c = Canvas::new(900, 550);
+    let mut c = Canvas::new(900, 550).with_origin(Origin::BottomLeft);
     let green = Color::new(0.0, 1.0, 0.0);
     while p.position.y > 0.0 {
         p = tick(&e, p);
         if p.position.x.is_sign_positive() && p.position.y.is_sign_positive() {
             let x = p.position.x as usize;
-            let y = c.height - p.position.y as usize;
+            let y = p.position.y as usize;
             if x < c.width && y < c.height {
                 c.write_pixel(x, y, green);
             }
@@ -16,7 +16,6 @@ fn main() {
     let radius = CANVAS_WIDTH as f64 * 3.0 / 8.0;
 
     (0..NUM_HOURS)
-        .into_iter()
         .map(|hour| {
             Matrix4::scaling(radius, 1.0, radius)
                 .rotate_y(2.0 * PI * (hour as f64 / NUM_HOURS as f64))
@@ -12,8 +12,6 @@ fn main() {
     let wall_z = 10.0;
     let wall_size = 7.0;
     let canvas_pixels = 100usize;
-    let pixel_size = wall_size / canvas_pixels as f64;
-    let half = wall_size / 2.0;
     let mut canvas = Canvas::new(canvas_pixels, canvas_pixels);
     let color = Color::new(1.0, 0.0, 0.0);
 
@@ -39,10 +37,8 @@ fn main() {
     //     .shear(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
 
     for y in 0..canvas.height {
-        let world_y = half - pixel_size * y as f64;
         for x in 0..canvas.width {
-            let world_x = -half + pixel_size * x as f64;
-            let position = Tuple::new_point(world_x, world_y, wall_z);
+            let position = canvas.pixel_to_world(x, y, wall_z, wall_size);
 
             let r = Ray::new(ray_origin, (position - ray_origin).normalize());
             let xs = shape.intersect(r);
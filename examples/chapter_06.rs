@@ -13,8 +13,6 @@ fn main() {
     let wall_z = 10.0;
     let wall_size = 7.0;
     let canvas_pixels = 100usize;
-    let pixel_size = wall_size / canvas_pixels as f64;
-    let half = wall_size / 2.0;
     let mut canvas = Canvas::new(canvas_pixels, canvas_pixels);
 
     let mut shape = Sphere::new();
@@ -25,10 +23,8 @@ fn main() {
     let light = PointLight::new(light_position, light_color);
 
     for y in 0..canvas.height {
-        let world_y = half - pixel_size * y as f64;
         for x in 0..canvas.width {
-            let world_x = -half + pixel_size * x as f64;
-            let position = Tuple::new_point(world_x, world_y, wall_z);
+            let position = canvas.pixel_to_world(x, y, wall_z, wall_size);
 
             let r = Ray::new(ray_origin, (position - ray_origin).normalize());
             let xs = shape.intersect(r);
@@ -40,7 +36,7 @@ fn main() {
                 let color = hit
                     .object
                     .material
-                    .lighting(light, point, eye, normal, false);
+                    .lighting(hit.object, light, point, eye, normal, false);
                 canvas.write_pixel(x, y, color);
             }
         }
@@ -0,0 +1,324 @@
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::tuple::Tuple;
+use crate::EPSILON;
+
+// An axis-aligned bounding box. The empty box has its minimum at +infinity and its maximum at
+// -infinity so that merging anything into it yields that thing.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Bounds {
+    pub minimum: Tuple,
+    pub maximum: Tuple,
+}
+
+impl Bounds {
+    pub fn new(minimum: Tuple, maximum: Tuple) -> Self {
+        Self { minimum, maximum }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            minimum: Tuple::new_point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            maximum: Tuple::new_point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+
+    pub fn infinite() -> Self {
+        Self {
+            minimum: Tuple::new_point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            maximum: Tuple::new_point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.minimum.x > self.maximum.x
+            || self.minimum.y > self.maximum.y
+            || self.minimum.z > self.maximum.z
+    }
+
+    pub fn contains_point(&self, p: Tuple) -> bool {
+        (self.minimum.x..=self.maximum.x).contains(&p.x)
+            && (self.minimum.y..=self.maximum.y).contains(&p.y)
+            && (self.minimum.z..=self.maximum.z).contains(&p.z)
+    }
+
+    pub fn contains_box(&self, other: &Bounds) -> bool {
+        self.contains_point(other.minimum) && self.contains_point(other.maximum)
+    }
+
+    pub fn add_point(&mut self, p: Tuple) {
+        self.minimum = Tuple::new_point(
+            self.minimum.x.min(p.x),
+            self.minimum.y.min(p.y),
+            self.minimum.z.min(p.z),
+        );
+        self.maximum = Tuple::new_point(
+            self.maximum.x.max(p.x),
+            self.maximum.y.max(p.y),
+            self.maximum.z.max(p.z),
+        );
+    }
+
+    pub fn merge(&mut self, other: &Bounds) {
+        self.add_point(other.minimum);
+        self.add_point(other.maximum);
+    }
+
+    // The box around all eight transformed corners. Boxes reaching infinity stay infinite on
+    // every axis, since the transform can mix an infinite axis into the others.
+    pub fn transform(&self, transform: Matrix4) -> Bounds {
+        if self.is_empty() {
+            return *self;
+        }
+        let (min, max) = (self.minimum, self.maximum);
+        let corners = [min.x, max.x].into_iter().flat_map(|x| {
+            [min.y, max.y]
+                .into_iter()
+                .flat_map(move |y| [min.z, max.z].map(|z| Tuple::new_point(x, y, z)))
+        });
+        let mut result = Bounds::empty();
+        for corner in corners {
+            let p = transform * corner;
+            if !(p.x.is_finite() && p.y.is_finite() && p.z.is_finite()) {
+                return Bounds::infinite();
+            }
+            result.add_point(p);
+        }
+        result
+    }
+
+    // Whether the ray's line passes through the box, behind the origin or in front of it.
+    pub fn intersects(&self, ray: Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let axes = [
+            (
+                ray.origin.x,
+                ray.direction.x,
+                self.minimum.x,
+                self.maximum.x,
+            ),
+            (
+                ray.origin.y,
+                ray.direction.y,
+                self.minimum.y,
+                self.maximum.y,
+            ),
+            (
+                ray.origin.z,
+                ray.direction.z,
+                self.minimum.z,
+                self.maximum.z,
+            ),
+        ];
+        let mut tmin = f64::NEG_INFINITY;
+        let mut tmax = f64::INFINITY;
+        for (origin, direction, minimum, maximum) in axes {
+            if direction.abs() < EPSILON {
+                if origin < minimum || origin > maximum {
+                    return false;
+                }
+            } else {
+                let t0 = (minimum - origin) / direction;
+                let t1 = (maximum - origin) / direction;
+                tmin = tmin.max(t0.min(t1));
+                tmax = tmax.min(t0.max(t1));
+            }
+        }
+        tmin <= tmax
+    }
+
+    pub fn pad(&self, margin: f64) -> Bounds {
+        let margin = Tuple::new_vector(margin, margin, margin);
+        Bounds::new(self.minimum - margin, self.maximum + margin)
+    }
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Bounds::empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounds::Bounds;
+    use crate::matrix::Matrix4;
+    use crate::ray::Ray;
+    use crate::tuple::Tuple;
+    use std::f64::consts::{PI, SQRT_2};
+
+    #[test]
+    fn creating_an_empty_bounding_box() {
+        let b = Bounds::empty();
+
+        assert_eq!(b.minimum.x, f64::INFINITY);
+        assert_eq!(b.maximum.x, f64::NEG_INFINITY);
+        assert!(!b.contains_point(Tuple::new_point(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn checking_whether_a_box_contains_a_point() {
+        let b = Bounds::new(
+            Tuple::new_point(5.0, -2.0, 0.0),
+            Tuple::new_point(11.0, 4.0, 7.0),
+        );
+        let cases = [
+            (Tuple::new_point(5.0, -2.0, 0.0), true),
+            (Tuple::new_point(11.0, 4.0, 7.0), true),
+            (Tuple::new_point(8.0, 1.0, 3.0), true),
+            (Tuple::new_point(3.0, 0.0, 3.0), false),
+            (Tuple::new_point(8.0, -4.0, 3.0), false),
+            (Tuple::new_point(8.0, 1.0, -1.0), false),
+            (Tuple::new_point(13.0, 1.0, 3.0), false),
+            (Tuple::new_point(8.0, 5.0, 3.0), false),
+            (Tuple::new_point(8.0, 1.0, 8.0), false),
+        ];
+
+        for (point, result) in cases {
+            assert_eq!(b.contains_point(point), result);
+        }
+    }
+
+    #[test]
+    fn checking_whether_a_box_contains_another_box() {
+        let b = Bounds::new(
+            Tuple::new_point(5.0, -2.0, 0.0),
+            Tuple::new_point(11.0, 4.0, 7.0),
+        );
+        let cases = [
+            (
+                Tuple::new_point(5.0, -2.0, 0.0),
+                Tuple::new_point(11.0, 4.0, 7.0),
+                true,
+            ),
+            (
+                Tuple::new_point(6.0, -1.0, 1.0),
+                Tuple::new_point(10.0, 3.0, 6.0),
+                true,
+            ),
+            (
+                Tuple::new_point(4.0, -3.0, -1.0),
+                Tuple::new_point(10.0, 3.0, 6.0),
+                false,
+            ),
+            (
+                Tuple::new_point(6.0, -1.0, 1.0),
+                Tuple::new_point(12.0, 5.0, 8.0),
+                false,
+            ),
+        ];
+
+        for (min, max, result) in cases {
+            assert_eq!(b.contains_box(&Bounds::new(min, max)), result);
+        }
+    }
+
+    #[test]
+    fn merging_two_bounding_boxes() {
+        let mut b1 = Bounds::new(
+            Tuple::new_point(-5.0, -2.0, 0.0),
+            Tuple::new_point(7.0, 4.0, 4.0),
+        );
+        let b2 = Bounds::new(
+            Tuple::new_point(8.0, -7.0, -2.0),
+            Tuple::new_point(14.0, 2.0, 8.0),
+        );
+        b1.merge(&b2);
+
+        assert_eq!(b1.minimum, Tuple::new_point(-5.0, -7.0, -2.0));
+        assert_eq!(b1.maximum, Tuple::new_point(14.0, 4.0, 8.0));
+    }
+
+    #[test]
+    fn merging_into_an_empty_box() {
+        let mut b = Bounds::empty();
+        let other = Bounds::new(
+            Tuple::new_point(-1.0, -1.0, -1.0),
+            Tuple::new_point(1.0, 1.0, 1.0),
+        );
+        b.merge(&other);
+
+        assert_eq!(b, other);
+    }
+
+    #[test]
+    fn padding_a_bounding_box() {
+        let b = Bounds::new(
+            Tuple::new_point(-1.0, 0.0, 2.0),
+            Tuple::new_point(1.0, 3.0, 4.0),
+        );
+        let padded = b.pad(0.5);
+
+        assert_eq!(padded.minimum, Tuple::new_point(-1.5, -0.5, 1.5));
+        assert_eq!(padded.maximum, Tuple::new_point(1.5, 3.5, 4.5));
+        assert!(padded.contains_box(&b));
+        assert!(padded.contains_point(Tuple::new_point(1.5, 3.5, 4.5)));
+    }
+
+    #[test]
+    fn transforming_a_bounding_box() {
+        let b = Bounds::new(
+            Tuple::new_point(-1.0, -1.0, -1.0),
+            Tuple::new_point(1.0, 1.0, 1.0),
+        );
+        let m = Matrix4::rotation_x(PI / 4.0) * Matrix4::rotation_y(PI / 4.0);
+        let b2 = b.transform(m);
+
+        assert!((b2.minimum.x + SQRT_2).abs() < 0.0001);
+        assert!((b2.minimum.y + 1.7071).abs() < 0.0001);
+        assert!((b2.minimum.z + 1.7071).abs() < 0.0001);
+        assert!((b2.maximum.x - SQRT_2).abs() < 0.0001);
+        assert!((b2.maximum.y - 1.7071).abs() < 0.0001);
+        assert!((b2.maximum.z - 1.7071).abs() < 0.0001);
+    }
+
+    #[test]
+    fn transforming_an_infinite_or_empty_bounding_box() {
+        let plane = Bounds::new(
+            Tuple::new_point(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
+            Tuple::new_point(f64::INFINITY, 0.0, f64::INFINITY),
+        );
+        let m = Matrix4::rotation_x(PI / 4.0);
+
+        assert_eq!(plane.transform(m).minimum.y, f64::NEG_INFINITY);
+        assert_eq!(plane.transform(m).maximum.y, f64::INFINITY);
+        assert!(Bounds::empty().transform(m).is_empty());
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_bounding_box_at_the_origin() {
+        let b = Bounds::new(
+            Tuple::new_point(-1.0, -1.0, -1.0),
+            Tuple::new_point(1.0, 1.0, 1.0),
+        );
+        let cases = [
+            ((5.0, 0.5, 0.0), (-1.0, 0.0, 0.0), true),
+            ((-5.0, 0.5, 0.0), (1.0, 0.0, 0.0), true),
+            ((0.5, 5.0, 0.0), (0.0, -1.0, 0.0), true),
+            ((0.5, -5.0, 0.0), (0.0, 1.0, 0.0), true),
+            ((0.5, 0.0, 5.0), (0.0, 0.0, -1.0), true),
+            ((0.5, 0.0, -5.0), (0.0, 0.0, 1.0), true),
+            ((0.0, 0.5, 0.0), (0.0, 0.0, 1.0), true),
+            ((-2.0, 0.0, 0.0), (2.0, 4.0, 6.0), false),
+            ((0.0, -2.0, 0.0), (6.0, 2.0, 4.0), false),
+            ((0.0, 0.0, -2.0), (4.0, 6.0, 2.0), false),
+            ((2.0, 0.0, 2.0), (0.0, 0.0, -1.0), false),
+            ((0.0, 2.0, 2.0), (0.0, -1.0, 0.0), false),
+            ((2.0, 2.0, 0.0), (-1.0, 0.0, 0.0), false),
+        ];
+
+        for ((ox, oy, oz), (dx, dy, dz), result) in cases {
+            let direction = Tuple::new_vector(dx, dy, dz).normalize();
+            let r = Ray::new(Tuple::new_point(ox, oy, oz), direction);
+            assert_eq!(b.intersects(r), result);
+        }
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        assert!(!Bounds::empty().intersects(r));
+        assert!(Bounds::infinite().intersects(r));
+    }
+}
@@ -1,9 +1,14 @@
 use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::integrator::{PathTraced, PixelIntegrator, SingleSample};
 use crate::matrix::Matrix4;
 use crate::ray::Ray;
 use crate::shape::Shape;
+use crate::stats::Stats;
 use crate::tuple::Tuple;
-use crate::world::World;
+use crate::world::{PathTermination, RenderContext, World};
+
+const PATH_TRACE_MIN_BOUNCES: usize = 3;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Camera {
@@ -38,9 +43,59 @@ impl Camera {
         }
     }
 
+    // The camera as seen in a flat mirror. Rendering the world from it gives what the mirror
+    // reflects, without tracing reflection rays. The reflection flips handedness, so the image
+    // comes out mirrored left to right, as it would in the mirror.
+    pub fn mirror_across(&self, plane_point: Tuple, plane_normal: Tuple) -> Camera {
+        let n = plane_normal.normalize();
+        let d = 2.0 * ((plane_point - Tuple::new_point(0.0, 0.0, 0.0)) * n);
+        let reflection = Matrix4::new([
+            [
+                1.0 - 2.0 * n.x * n.x,
+                -2.0 * n.x * n.y,
+                -2.0 * n.x * n.z,
+                d * n.x,
+            ],
+            [
+                -2.0 * n.y * n.x,
+                1.0 - 2.0 * n.y * n.y,
+                -2.0 * n.y * n.z,
+                d * n.y,
+            ],
+            [
+                -2.0 * n.z * n.x,
+                -2.0 * n.z * n.y,
+                1.0 - 2.0 * n.z * n.z,
+                d * n.z,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        Camera {
+            transform: self.transform * reflection,
+            ..*self
+        }
+    }
+
+    pub fn eye_position(&self) -> Tuple {
+        self.transform.inverse() * Tuple::new_point(0.0, 0.0, 0.0)
+    }
+
+    pub fn forward(&self) -> Tuple {
+        (self.transform.inverse() * Tuple::new_vector(0.0, 0.0, -1.0)).normalize()
+    }
+
+    // The counterpart of World::convert_handedness. Converting both leaves the image unchanged.
+    pub fn convert_handedness(&mut self) {
+        self.transform = self.transform * Matrix4::flip_handedness();
+    }
+
     pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
-        let xoffset = (px as f64 + 0.5) * self.pixel_size;
-        let yoffset = (py as f64 + 0.5) * self.pixel_size;
+        self.ray_for_subpixel(px, py, 0.5, 0.5)
+    }
+
+    pub fn ray_for_subpixel(&self, px: usize, py: usize, dx: f64, dy: f64) -> Ray {
+        let xoffset = (px as f64 + dx) * self.pixel_size;
+        let yoffset = (py as f64 + dy) * self.pixel_size;
         let world_x = self.half_width - xoffset;
         let world_y = self.half_height - yoffset;
         let pixel = self.transform.inverse() * Tuple::new_point(world_x, world_y, -1.0);
@@ -49,28 +104,292 @@ impl Camera {
         Ray::new(origin, direction)
     }
 
+    // The near corners come first, then the far ones, each as top left, top right, bottom left
+    // and bottom right of the canvas. near and far are distances along the optical axis.
+    pub fn frustum_corners(&self, near: f64, far: f64) -> [Tuple; 8] {
+        let inverse = self.transform.inverse();
+        let corner = |distance: f64, sx: f64, sy: f64| {
+            inverse
+                * Tuple::new_point(
+                    sx * self.half_width * distance,
+                    sy * self.half_height * distance,
+                    -distance,
+                )
+        };
+        [
+            corner(near, 1.0, 1.0),
+            corner(near, -1.0, 1.0),
+            corner(near, 1.0, -1.0),
+            corner(near, -1.0, -1.0),
+            corner(far, 1.0, 1.0),
+            corner(far, -1.0, 1.0),
+            corner(far, 1.0, -1.0),
+            corner(far, -1.0, -1.0),
+        ]
+    }
+
     pub fn render<S: Shape>(&self, world: World<S>) -> Canvas {
+        self.render_rows(world, 0, self.vsize)
+    }
+
+    // Renders as if the world only held the objects passing the predicate, so the others neither
+    // show up nor cast shadows.
+    pub fn render_filtered<S: Shape, F: Fn(&S) -> bool>(
+        &self,
+        mut world: World<S>,
+        include: F,
+    ) -> Canvas {
+        world.objects.retain(|object| include(object));
+        self.render(world)
+    }
+
+    pub fn render_rows<S: Shape>(&self, world: World<S>, y_start: usize, y_end: usize) -> Canvas {
+        assert!(
+            y_start <= y_end,
+            "row range starts at {} after it ends at {}",
+            y_start,
+            y_end
+        );
+        assert!(
+            y_end <= self.vsize,
+            "row range ends at {} past the image height {}",
+            y_end,
+            self.vsize
+        );
+        let mut image = Canvas::new(self.hsize, y_end - y_start);
+        let mut context = RenderContext::new();
+
+        for y in y_start..y_end {
+            for x in 0..self.hsize {
+                let ray = self.ray_for_pixel(x, y);
+                let color = world.color_at_with(ray, &mut context);
+                #[cfg(feature = "nan-check")]
+                self.check_finite(color, x, y);
+                image.write_pixel(x, y - y_start, color);
+            }
+        }
+        image
+    }
+
+    pub fn render_into<S: Shape>(&self, world: World<S>, buf: &mut [Color], stride: usize) {
+        assert!(
+            stride >= self.hsize,
+            "stride {} is smaller than the image width {}",
+            stride,
+            self.hsize
+        );
+        assert!(
+            buf.len() >= stride * self.vsize,
+            "buffer of {} pixels cannot hold {} rows of stride {}",
+            buf.len(),
+            self.vsize,
+            stride
+        );
+
+        let mut context = RenderContext::new();
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let ray = self.ray_for_pixel(x, y);
+                let color = world.color_at_with(ray, &mut context);
+                #[cfg(feature = "nan-check")]
+                self.check_finite(color, x, y);
+                buf[y * stride + x] = color;
+            }
+        }
+    }
+
+    pub fn render_integrated<S: Shape, I: PixelIntegrator>(
+        &self,
+        world: World<S>,
+        integrator: I,
+    ) -> Canvas {
+        self.render_world(&world, integrator)
+    }
+
+    pub fn render_path_traced<S: Shape>(&self, world: World<S>, samples: usize) -> Canvas {
+        let integrator = PathTraced {
+            samples,
+            termination: PathTermination::RussianRoulette {
+                min_bounces: PATH_TRACE_MIN_BOUNCES,
+            },
+            next_event: true,
+        };
+        self.render_world(&world, integrator)
+    }
+
+    // Also records which pixels hit something in the canvas coverage channel.
+    pub fn render_with_alpha<S: Shape>(&self, world: World<S>) -> Canvas {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let (color, hit) = world.color_and_hit(self.ray_for_pixel(x, y));
+                #[cfg(feature = "nan-check")]
+                self.check_finite(color, x, y);
+                image.write_pixel(x, y, color);
+                image.write_alpha(x, y, if hit.is_some() { 1.0 } else { 0.0 });
+            }
+        }
+        image
+    }
+
+    // The reflection pass on its own, for compositing: each pixel is what its hit reflects.
+    pub fn render_reflections_only<S: Shape>(&self, world: World<S>) -> Canvas {
         let mut image = Canvas::new(self.hsize, self.vsize);
 
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let color = world.reflection_at(self.ray_for_pixel(x, y));
+                #[cfg(feature = "nan-check")]
+                self.check_finite(color, x, y);
+                image.write_pixel(x, y, color);
+            }
+        }
+        image
+    }
+
+    // Shades as usual, then draws black outlines where a pixel's hit differs from a neighbor's:
+    // one of them misses, their depths differ by more than edge_threshold relative to the nearer
+    // one, or their normals differ by more than edge_threshold (one minus the cosine).
+    pub fn render_toon<S: Shape>(&self, world: World<S>, edge_threshold: f64) -> Canvas {
+        let mut surfaces = Vec::with_capacity(self.hsize * self.vsize);
+        let mut image = Canvas::new(self.hsize, self.vsize);
         for y in 0..self.vsize {
             for x in 0..self.hsize {
                 let ray = self.ray_for_pixel(x, y);
-                let color = world.color_at(ray);
+                let xs = world.intersect_world(ray);
+                surfaces.push(xs.hit().map(|hit| {
+                    let comps = hit.prepare_computations(ray);
+                    (comps.t, comps.normalv)
+                }));
+                image.write_pixel(x, y, world.color_at(ray));
+            }
+        }
+
+        let is_edge = |a: Option<(f64, Tuple)>, b: Option<(f64, Tuple)>| match (a, b) {
+            (Some((ta, na)), Some((tb, nb))) => {
+                (ta - tb).abs() / ta.min(tb) > edge_threshold || 1.0 - na * nb > edge_threshold
+            }
+            (None, None) => false,
+            _ => true,
+        };
+        let black = Color::new(0.0, 0.0, 0.0);
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let surface = surfaces[y * self.hsize + x];
+                let neighbors = [
+                    (x > 0).then(|| surfaces[y * self.hsize + x - 1]),
+                    (x + 1 < self.hsize).then(|| surfaces[y * self.hsize + x + 1]),
+                    (y > 0).then(|| surfaces[(y - 1) * self.hsize + x]),
+                    (y + 1 < self.vsize).then(|| surfaces[(y + 1) * self.hsize + x]),
+                ];
+                // Only the nearer side of a depth step is outlined, so silhouettes stay one
+                // pixel wide and sit on the object.
+                let outlined = neighbors.into_iter().flatten().any(|neighbor| {
+                    let nearer = match (surface, neighbor) {
+                        (Some((t, _)), Some((n, _))) => t <= n,
+                        (Some(_), None) => true,
+                        _ => false,
+                    };
+                    nearer && is_edge(surface, neighbor)
+                });
+                if outlined {
+                    image.write_pixel(x, y, black);
+                }
+            }
+        }
+        image
+    }
+
+    pub fn render_with_stats<S: Shape>(&self, world: World<S>) -> (Canvas, Stats) {
+        world.stats.reset();
+        let image = self.render_world(&world, SingleSample);
+        (image, world.stats)
+    }
+
+    pub fn render_stereo<S: Shape>(
+        &self,
+        world: World<S>,
+        eye_separation: f64,
+    ) -> (Canvas, Canvas) {
+        let left = self
+            .eye(-eye_separation / 2.0)
+            .render_world(&world, SingleSample);
+        let right = self
+            .eye(eye_separation / 2.0)
+            .render_world(&world, SingleSample);
+        (left, right)
+    }
+
+    // The left eye's red channel with the right eye's green and blue.
+    pub fn render_anaglyph<S: Shape>(&self, world: World<S>, eye_separation: f64) -> Canvas {
+        let (left, right) = self.render_stereo(world, eye_separation);
+        let mut image = Canvas::new(self.hsize, self.vsize);
+
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let (l, r) = (left.pixel_at(x, y), right.pixel_at(x, y));
+                image.write_pixel(x, y, Color::new(l.red, r.green, r.blue));
+            }
+        }
+        image
+    }
+
+    // The camera moved sideways by offset. Pixel x grows towards camera space -x, so that is the
+    // camera's right and a positive offset moves it to the right.
+    fn eye(&self, offset: f64) -> Camera {
+        Camera {
+            transform: Matrix4::translation(offset, 0.0, 0.0) * self.transform,
+            ..*self
+        }
+    }
+
+    fn render_world<S: Shape, I: PixelIntegrator>(
+        &self,
+        world: &World<S>,
+        integrator: I,
+    ) -> Canvas {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        let emitters = world.emitters();
+
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let color = integrator.integrate(self, world, &emitters, x, y);
+                #[cfg(feature = "nan-check")]
+                self.check_finite(color, x, y);
                 image.write_pixel(x, y, color);
             }
         }
         image
     }
+
+    #[cfg(feature = "nan-check")]
+    fn check_finite(&self, color: Color, x: usize, y: usize) {
+        assert!(
+            color.is_finite(),
+            "non-finite color {:?} at pixel ({}, {}) for ray {:?}",
+            color,
+            x,
+            y,
+            self.ray_for_pixel(x, y)
+        );
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::assert_float_eq;
     use crate::camera::Camera;
+    use crate::canvas::Canvas;
     use crate::color::Color;
+    use crate::group::Group;
+    use crate::integrator::SingleSample;
+    use crate::light::PointLight;
     use crate::matrix::Matrix4;
+    use crate::plane::Plane;
+    use crate::sphere::Sphere;
     use crate::tuple::Tuple;
-    use crate::world::default_world;
+    use crate::world::{default_world, Background, BackgroundCache, World, WorldShape};
     use std::f64::consts::PI;
 
     #[test]
@@ -131,6 +450,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn constructing_a_ray_through_a_sub_pixel_offset() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        let r = c.ray_for_subpixel(100, 50, 0.5, 0.5);
+
+        assert_eq!(r.direction, c.ray_for_pixel(100, 50).direction);
+
+        let r = c.ray_for_subpixel(0, 0, 0.0, 0.0);
+        let expected = Tuple::new_vector(c.half_width, c.half_height, -1.0).normalize();
+
+        assert_eq!(r.direction, expected);
+    }
+
     #[test]
     fn rendering_a_world_with_camera() {
         let w = default_world();
@@ -143,4 +475,512 @@ mod tests {
 
         assert_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
     }
+
+    #[test]
+    fn rendering_bands_of_rows_reproduces_the_full_image() {
+        let w = default_world();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let half = c.vsize / 2;
+        let top = c.render_rows(w.clone(), 0, half);
+        let bottom = c.render_rows(w.clone(), half, c.vsize);
+        let image = c.render(w);
+
+        assert_eq!(top.height, half);
+        assert_eq!(bottom.height, c.vsize - half);
+        for y in 0..c.vsize {
+            for x in 0..c.hsize {
+                let band = if y < half {
+                    top.pixel_at(x, y)
+                } else {
+                    bottom.pixel_at(x, y - half)
+                };
+                assert_eq!(band, image.pixel_at(x, y));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "after it ends")]
+    fn rendering_a_reversed_band_of_rows() {
+        let c = Camera::new(11, 11, PI / 2.0);
+
+        c.render_rows(default_world(), 6, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "past the image height")]
+    fn rendering_rows_below_the_image() {
+        let c = Camera::new(11, 11, PI / 2.0);
+
+        c.render_rows(default_world(), 6, 12);
+    }
+
+    #[test]
+    fn rendering_into_a_caller_provided_buffer() {
+        let w = default_world();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let stride = 16;
+        let padding = Color::new(1.0, 0.0, 1.0);
+        let mut buf = vec![padding; stride * c.vsize];
+        c.render_into(w.clone(), &mut buf, stride);
+        let image = c.render(w);
+
+        for y in 0..c.vsize {
+            for x in 0..stride {
+                let expected = if x < c.hsize {
+                    image.pixel_at(x, y)
+                } else {
+                    padding
+                };
+                assert_eq!(buf[y * stride + x], expected);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot hold")]
+    fn rendering_into_a_buffer_that_is_too_small() {
+        let w = default_world();
+        let c = Camera::new(11, 11, PI / 2.0);
+        let mut buf = vec![Color::new(0.0, 0.0, 0.0); 11 * 10];
+
+        c.render_into(w, &mut buf, 11);
+    }
+
+    #[test]
+    fn rendering_with_stats_counts_rays_and_intersection_tests() {
+        let w = default_world();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+        let to = Tuple::new_point(0.0, 0.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        c.transform = Matrix4::view_transform(from, to, up);
+        let (image, stats) = c.render_with_stats(w.clone());
+
+        assert_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
+        assert_eq!(stats.primary_rays(), 121);
+        assert!(stats.shadow_rays() > 0);
+        assert!(stats.intersection_tests() >= 2 * 121);
+
+        let mut w = w;
+        for object in &mut w.objects {
+            object.material.cast_shadow = false;
+        }
+        let mut g = Group::new();
+        for object in &w.objects {
+            g.add_child(object.clone());
+        }
+        let mut grouped: World = World::new();
+        grouped.light = w.light;
+        grouped.objects.push(g.into());
+        let (_, stats) = c.render_with_stats(w);
+
+        assert_eq!(stats.primary_rays(), 121);
+        assert_eq!(stats.shadow_rays(), 0);
+
+        let (_, stats) = c.render_with_stats(grouped);
+
+        assert_eq!(stats.primary_rays(), 121);
+        assert_eq!(stats.shadow_rays(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "nan-check")]
+    #[should_panic(expected = "at pixel (5, 4) for ray")]
+    fn nan_check_reports_the_first_pixel_shaded_with_a_nan() {
+        let mut w = default_world();
+        w.objects[0].material.ambient = f64::NAN;
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+        let to = Tuple::new_point(0.0, 0.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        c.transform = Matrix4::view_transform(from, to, up);
+        c.render(w);
+    }
+
+    #[test]
+    fn mirroring_a_camera_across_the_xz_plane() {
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(1.0, 2.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let m = c.mirror_across(
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let camera_to_world = c.transform.inverse();
+        let mirrored_to_world = m.transform.inverse();
+        let eye = mirrored_to_world * Tuple::new_point(0.0, 0.0, 0.0);
+        let up = camera_to_world * Tuple::new_vector(0.0, 1.0, 0.0);
+        let mirrored_up = mirrored_to_world * Tuple::new_vector(0.0, 1.0, 0.0);
+
+        assert_eq!(eye, Tuple::new_point(1.0, -2.0, -5.0));
+        assert_eq!(mirrored_up, Tuple::new_vector(up.x, -up.y, up.z));
+        assert!(mirrored_up.y < 0.0);
+        assert_eq!(m.hsize, c.hsize);
+        assert_eq!(m.pixel_size, c.pixel_size);
+    }
+
+    #[test]
+    fn a_mirrored_camera_sees_the_reflected_point() {
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 3.0, -5.0),
+            Tuple::new_point(0.0, 1.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let m = c.mirror_across(
+            Tuple::new_point(0.0, 1.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let r = m.ray_for_pixel(5, 5);
+
+        assert_eq!(r.origin, Tuple::new_point(0.0, -1.0, -5.0));
+        assert_eq!(
+            r.direction,
+            (Tuple::new_point(0.0, 1.0, 0.0) - r.origin).normalize()
+        );
+    }
+
+    #[test]
+    fn the_frustum_corners_of_an_untransformed_camera() {
+        let c = Camera::new(200, 100, PI / 2.0);
+        let corners = c.frustum_corners(1.0, 10.0);
+
+        assert_eq!(corners[0], Tuple::new_point(1.0, 0.5, -1.0));
+        assert_eq!(corners[1], Tuple::new_point(-1.0, 0.5, -1.0));
+        assert_eq!(corners[2], Tuple::new_point(1.0, -0.5, -1.0));
+        assert_eq!(corners[3], Tuple::new_point(-1.0, -0.5, -1.0));
+        assert_eq!(corners[7], Tuple::new_point(-10.0, -5.0, -10.0));
+    }
+
+    #[test]
+    fn the_frustum_corners_lie_on_the_corner_rays() {
+        let mut c = Camera::new(201, 101, PI / 2.0);
+        c.transform = Matrix4::rotation_y(PI / 4.0) * Matrix4::translation(0.0, -2.0, 5.0);
+        let corners = c.frustum_corners(0.5, 20.0);
+        let r = c.ray_for_subpixel(0, 0, 0.0, 0.0);
+        let far = corners[4] - r.origin;
+
+        assert_eq!((corners[0] - r.origin).normalize(), r.direction);
+        assert_eq!(far.normalize(), r.direction);
+        let r = c.ray_for_subpixel(200, 100, 1.0, 1.0);
+
+        assert_eq!((corners[3] - r.origin).normalize(), r.direction);
+    }
+
+    #[test]
+    fn the_eyes_are_offset_along_the_cameras_right() {
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let left = c.eye(-0.5).ray_for_pixel(5, 5);
+        let right = c.eye(0.5).ray_for_pixel(5, 5);
+        let edge = c.ray_for_pixel(10, 5);
+
+        assert_eq!(left.origin, Tuple::new_point(-0.5, 0.0, -5.0));
+        assert_eq!(right.origin, Tuple::new_point(0.5, 0.0, -5.0));
+        assert!(edge.direction.x > 0.0);
+    }
+
+    #[test]
+    fn an_anaglyph_takes_red_from_the_left_eye_and_cyan_from_the_right() {
+        let w = default_world();
+        let mut c = Camera::new(21, 11, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let image = c.render_anaglyph(w.clone(), 1.0);
+        let left = c.eye(-0.5).render(w.clone());
+        let right = c.eye(0.5).render(w);
+
+        let mut differs = false;
+        for y in 0..c.vsize {
+            for x in 0..c.hsize {
+                let (l, r) = (left.pixel_at(x, y), right.pixel_at(x, y));
+                let pixel = image.pixel_at(x, y);
+                assert_eq!(pixel, Color::new(l.red, r.green, r.blue));
+                differs |= l != r;
+            }
+        }
+        assert!(differs);
+    }
+
+    #[test]
+    fn rendering_a_stereo_pair() {
+        let w = default_world();
+        let mut c = Camera::new(21, 11, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let pixels = |image: &Canvas| {
+            (0..c.vsize)
+                .flat_map(|y| (0..c.hsize).map(move |x| (x, y)))
+                .map(|(x, y)| image.pixel_at(x, y))
+                .collect::<Vec<_>>()
+        };
+        let (left, right) = c.render_stereo(w.clone(), 1.0);
+
+        assert_ne!(pixels(&left), pixels(&right));
+
+        let (left, right) = c.render_stereo(w.clone(), 0.0);
+
+        assert_eq!(pixels(&left), pixels(&right));
+        assert_eq!(pixels(&left), pixels(&c.render(w)));
+    }
+
+    #[test]
+    fn rendering_with_alpha_records_coverage() {
+        let w = default_world();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let image = c.render_with_alpha(w.clone());
+        let plain = c.render(w);
+
+        assert_eq!(image.alpha_at(5, 5), 1.0);
+        assert_eq!(image.alpha_at(0, 0), 0.0);
+        assert_eq!(image.pixel_at(5, 5), plain.pixel_at(5, 5));
+        assert!(!plain.has_alpha());
+    }
+
+    #[test]
+    fn shapes_inside_groups_count_as_coverage() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        let mut w: World = World::new();
+        w.light = default_world().light;
+        w.objects.push(g.into());
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let image = c.render_with_alpha(w);
+
+        assert_eq!(image.alpha_at(5, 5), 1.0);
+        assert_eq!(image.alpha_at(0, 0), 0.0);
+    }
+
+    #[test]
+    fn rendering_only_reflections() {
+        let mut floor = Plane::new();
+        floor.material.reflective = 0.5;
+        let mut ball = Sphere::new();
+        ball.transform = Matrix4::translation(0.0, 1.0, 0.0);
+        let mut w: World = World::new();
+        w.light = default_world().light;
+        w.background = Background::Solid(Color::new(1.0, 1.0, 1.0));
+        w.objects.push(floor.into());
+        w.objects.push(ball.into());
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 1.5, -5.0),
+            Tuple::new_point(0.0, 1.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let image = c.render_reflections_only(w);
+        let black = Color::new(0.0, 0.0, 0.0);
+
+        assert_ne!(image.pixel_at(0, 10), black);
+        assert_ne!(image.pixel_at(5, 10), black);
+        assert_eq!(image.pixel_at(5, 5), black);
+        assert_eq!(image.pixel_at(0, 0), black);
+    }
+
+    #[test]
+    fn the_background_cache_leaves_the_image_unchanged() {
+        let mut ball = Sphere::new();
+        ball.transform = Matrix4::scaling(0.2, 0.2, 0.2);
+        let mut w: World = World::new();
+        w.light = default_world().light;
+        w.background = Background::Gradient {
+            bottom: Color::new(0.2, 0.1, 0.0),
+            top: Color::new(0.4, 0.6, 1.0),
+        };
+        w.objects.push(ball.into());
+        let mut c = Camera::new(21, 21, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let plain = c.render(w.clone());
+        w.background_cache = Some(BackgroundCache::new(64));
+        let cached = c.render(w);
+
+        for y in 0..c.vsize {
+            for x in 0..c.hsize {
+                let (a, b) = (plain.pixel_at(x, y), cached.pixel_at(x, y));
+                assert_eq!((a.red, a.green, a.blue), (b.red, b.green, b.blue));
+            }
+        }
+    }
+
+    #[test]
+    fn path_tracing_a_closed_glowing_room_converges() {
+        let mut room = Sphere::new();
+        room.transform = Matrix4::scaling(10.0, 10.0, 10.0);
+        room.material.emissive = Color::new(0.5, 0.5, 0.5);
+        room.material.diffuse = 0.5;
+        let mut ball = Sphere::new();
+        ball.material.color = Color::new(0.2, 0.4, 0.8);
+        let mut w = World::new();
+        w.objects.push(room);
+        w.objects.push(ball);
+        let mut c = Camera::new(5, 5, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let mean = |image: &Canvas| {
+            let mut sum = 0.0;
+            for y in 0..c.vsize {
+                for x in 0..c.hsize {
+                    let p = image.pixel_at(x, y);
+                    assert!(p.luminance() > 0.0);
+                    // On average nothing is brighter than the room's 0.5 / (1 - 0.5); leave
+                    // room for the noise.
+                    assert!(p.red < 2.0 && p.green < 2.0 && p.blue < 2.0);
+                    sum += p.luminance();
+                }
+            }
+            sum / (c.hsize * c.vsize) as f64
+        };
+        let coarse = mean(&c.render_path_traced(w.clone(), 16));
+        let fine = mean(&c.render_path_traced(w, 256));
+
+        assert!((coarse - fine).abs() < 0.05);
+    }
+
+    #[test]
+    fn rendering_with_a_render_context_matches_the_integrator_path() {
+        let w = default_world();
+        let mut c = Camera::new(21, 11, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let image = c.render(w.clone());
+        let expected = c.render_integrated(w, SingleSample);
+
+        for y in 0..c.vsize {
+            for x in 0..c.hsize {
+                assert_eq!(image.pixel_at(x, y), expected.pixel_at(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn toon_rendering_outlines_the_silhouette() {
+        let w = default_world();
+        let mut c = Camera::new(41, 41, PI / 4.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let image = c.render_toon(w.clone(), 0.2);
+        let plain = c.render(w.clone());
+        let black = Color::new(0.0, 0.0, 0.0);
+
+        assert_eq!(image.pixel_at(20, 20), plain.pixel_at(20, 20));
+        let edge = (0..20)
+            .find(|&x| w.intersect_world(c.ray_for_pixel(x, 20)).hit().is_some())
+            .unwrap();
+
+        assert_ne!(plain.pixel_at(edge, 20), black);
+        assert_eq!(image.pixel_at(edge, 20), black);
+        assert_eq!(image.pixel_at(edge + 3, 20), plain.pixel_at(edge + 3, 20));
+    }
+
+    #[test]
+    fn converting_the_handedness_of_world_and_camera_keeps_the_image() {
+        let mut w = default_world();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(1.0, 1.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let before = c.render(w.clone());
+        w.convert_handedness();
+        c.convert_handedness();
+        let after = c.render(w);
+
+        for y in 0..c.vsize {
+            for x in 0..c.hsize {
+                assert_eq!(after.pixel_at(x, y), before.pixel_at(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn the_eye_position_and_forward_vector_of_a_camera() {
+        let from = Tuple::new_point(1.0, 3.0, 2.0);
+        let to = Tuple::new_point(4.0, -2.0, 8.0);
+        let mut c = Camera::new(160, 120, PI / 2.0);
+        c.transform = Matrix4::view_transform(from, to, Tuple::new_vector(1.0, 1.0, 0.0));
+
+        assert_eq!(c.eye_position(), from);
+        assert_eq!(c.forward(), (to - from).normalize());
+    }
+
+    #[test]
+    fn the_default_camera_looks_down_negative_z() {
+        let c = Camera::new(160, 120, PI / 2.0);
+
+        assert_eq!(c.eye_position(), Tuple::new_point(0.0, 0.0, 0.0));
+        assert_eq!(c.forward(), Tuple::new_vector(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn rendering_only_the_floor() {
+        let mut w: World = World::new();
+        w.light = Some(PointLight::new(
+            Tuple::new_point(-10.0, 10.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let mut sphere = Sphere::new();
+        sphere.transform = Matrix4::translation(0.0, 1.0, 0.0);
+        w.objects.push(sphere.into());
+        w.objects.push(Plane::new().into());
+        let mut c = Camera::new(21, 21, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 1.0, -5.0),
+            Tuple::new_point(0.0, 1.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let full = c.render(w.clone());
+        let floor = c.render_filtered(w, |shape| matches!(shape, WorldShape::Plane(_)));
+
+        assert_ne!(full.pixel_at(10, 10), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(floor.pixel_at(10, 10), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(floor.pixel_at(10, 20), full.pixel_at(10, 20));
+    }
 }
@@ -1,10 +1,20 @@
 use crate::color::Color;
+use crate::tuple::Tuple;
 use std::io::Write;
 
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Origin {
+    #[default]
+    TopLeft,
+    BottomLeft,
+}
+
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
+    pub origin: Origin,
     pixels: Vec<Color>,
+    alpha: Option<Vec<f64>>,
 }
 
 impl Canvas {
@@ -12,12 +22,23 @@ impl Canvas {
         Self {
             width,
             height,
+            origin: Origin::TopLeft,
             pixels: vec![Color::new(0.0, 0.0, 0.0); width * height],
+            alpha: None,
         }
     }
 
+    pub fn with_origin(mut self, origin: Origin) -> Self {
+        self.origin = origin;
+        self
+    }
+
     fn coordinate_to_index(&self, x: usize, y: usize) -> usize {
-        y * self.width + x
+        let row = match self.origin {
+            Origin::TopLeft => y,
+            Origin::BottomLeft => self.height - 1 - y,
+        };
+        row * self.width + x
     }
 
     pub fn write_pixel(&mut self, x: usize, y: usize, color: Color) {
@@ -29,6 +50,143 @@ impl Canvas {
         self.pixels[self.coordinate_to_index(x, y)]
     }
 
+    // The coverage channel is only allocated once some alpha is written. Until then every pixel
+    // is opaque.
+    pub fn write_alpha(&mut self, x: usize, y: usize, alpha: f64) {
+        let index = self.coordinate_to_index(x, y);
+        let size = self.width * self.height;
+        self.alpha.get_or_insert_with(|| vec![1.0; size])[index] = alpha;
+    }
+
+    pub fn alpha_at(&self, x: usize, y: usize) -> f64 {
+        match &self.alpha {
+            Some(alpha) => alpha[self.coordinate_to_index(x, y)],
+            None => 1.0,
+        }
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.alpha.is_some()
+    }
+
+    // Maps a pixel onto a wall of the given width, centered on the z axis at wall_z.
+    pub fn pixel_to_world(&self, x: usize, y: usize, wall_z: f64, wall_size: f64) -> Tuple {
+        let pixel_size = wall_size / self.width as f64;
+        let half_width = wall_size / 2.0;
+        let half_height = pixel_size * self.height as f64 / 2.0;
+        let world_x = -half_width + pixel_size * x as f64;
+        let world_y = half_height - pixel_size * y as f64;
+        Tuple::new_point(world_x, world_y, wall_z)
+    }
+
+    // Blends by the coverage channel when there is one. Otherwise renders leave the pixels of rays
+    // that missed everything black, so those are treated as uncovered and replaced.
+    pub fn composite_over(&self, background: Color) -> Canvas {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let pixels = match &self.alpha {
+            Some(alpha) => self
+                .pixels
+                .iter()
+                .zip(alpha.iter())
+                .map(|(&pixel, &alpha)| pixel * alpha + background * (1.0 - alpha))
+                .collect(),
+            None => self
+                .pixels
+                .iter()
+                .map(|&pixel| if pixel == black { background } else { pixel })
+                .collect(),
+        };
+        Canvas {
+            width: self.width,
+            height: self.height,
+            origin: self.origin,
+            pixels,
+            alpha: None,
+        }
+    }
+
+    pub fn diff(&self, other: &Canvas, amplify: f64) -> Canvas {
+        assert!(
+            self.width == other.width && self.height == other.height,
+            "cannot diff a {}x{} canvas with a {}x{} canvas",
+            self.width,
+            self.height,
+            other.width,
+            other.height
+        );
+        let mut result = Canvas::new(self.width, self.height).with_origin(self.origin);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (a, b) = (self.pixel_at(x, y), other.pixel_at(x, y));
+                let difference = Color::new(
+                    (a.red - b.red).abs(),
+                    (a.green - b.green).abs(),
+                    (a.blue - b.blue).abs(),
+                );
+                result.write_pixel(x, y, difference * amplify);
+            }
+        }
+        result
+    }
+
+    // Radiance RGBE: a shared exponent keeps values above 1.0. Scanlines that the format allows
+    // to be run-length encoded are written as literal-only runs, since a flat scanline that
+    // happened to start with 2, 2 would be misread as encoded.
+    pub fn to_hdr(&self) -> Vec<u8> {
+        let mut result = Vec::new();
+        write!(
+            &mut result,
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            self.height, self.width
+        )
+        .unwrap();
+
+        let encoded = (8..0x8000).contains(&self.width);
+        for row in self.pixels.chunks(self.width) {
+            let rgbe: Vec<_> = row.iter().map(|pixel| Self::to_rgbe(*pixel)).collect();
+            if !encoded {
+                result.extend(rgbe.iter().flatten());
+                continue;
+            }
+            result.extend([2, 2, (self.width >> 8) as u8, self.width as u8]);
+            for channel in 0..4 {
+                let bytes: Vec<_> = rgbe.iter().map(|pixel| pixel[channel]).collect();
+                for chunk in bytes.chunks(128) {
+                    result.push(chunk.len() as u8);
+                    result.extend(chunk);
+                }
+            }
+        }
+        result
+    }
+
+    // Channels past what the format can hold, infinite ones included, are clamped to its largest
+    // value. NaN is written as black.
+    fn to_rgbe(pixel: Color) -> [u8; 4] {
+        // 255 in each mantissa byte and in the exponent byte.
+        let max = 255.0 * 2f64.powi(255 - 128 - 8);
+        let clamp = |channel: f64| {
+            if channel.is_nan() {
+                0.0
+            } else {
+                channel.clamp(0.0, max)
+            }
+        };
+        let (red, green, blue) = (clamp(pixel.red), clamp(pixel.green), clamp(pixel.blue));
+        let largest = red.max(green).max(blue);
+        if largest < 1e-32 {
+            return [0, 0, 0, 0];
+        }
+        let exponent = largest.log2().floor() as i32 + 1;
+        let scale = 256.0 / 2f64.powi(exponent);
+        [
+            (red * scale).min(255.0) as u8,
+            (green * scale).min(255.0) as u8,
+            (blue * scale).min(255.0) as u8,
+            (exponent + 128) as u8,
+        ]
+    }
+
     fn scale_component(component: f64) -> u8 {
         (component * 255.0).clamp(0.0, 255.0).round() as u8
     }
@@ -71,8 +229,9 @@ impl Canvas {
 
 #[cfg(test)]
 mod tests {
-    use crate::canvas::Canvas;
+    use crate::canvas::{Canvas, Origin};
     use crate::color::Color;
+    use crate::tuple::Tuple;
 
     #[test]
     fn creating_a_canvas() {
@@ -92,6 +251,192 @@ mod tests {
         assert_eq!(c.pixel_at(2, 3), red);
     }
 
+    #[test]
+    fn a_canvas_defaults_to_a_top_left_origin() {
+        let c = Canvas::new(10, 20);
+
+        assert_eq!(c.origin, Origin::TopLeft);
+    }
+
+    #[test]
+    fn writing_pixels_to_a_bottom_left_canvas() {
+        let mut c = Canvas::new(5, 3).with_origin(Origin::BottomLeft);
+        let red = Color::new(1.0, 0.0, 0.0);
+        c.write_pixel(0, 0, red);
+
+        assert_eq!(c.pixel_at(0, 0), red);
+        let ppm = String::from_utf8(c.to_ppm()).unwrap();
+        let rows: Vec<_> = ppm.lines().skip(3).collect();
+        assert_eq!(rows[0], "0 0 0 0 0 0 0 0 0 0 0 0 0 0 0");
+        assert_eq!(rows[2], "255 0 0 0 0 0 0 0 0 0 0 0 0 0 0");
+    }
+
+    #[test]
+    fn mapping_the_center_pixel_onto_a_wall() {
+        let c = Canvas::new(100, 100);
+
+        assert_eq!(
+            c.pixel_to_world(50, 50, 10.0, 7.0),
+            Tuple::new_point(0.0, 0.0, 10.0)
+        );
+        assert_eq!(
+            c.pixel_to_world(0, 0, 10.0, 7.0),
+            Tuple::new_point(-3.5, 3.5, 10.0)
+        );
+    }
+
+    #[test]
+    fn compositing_a_canvas_over_a_background() {
+        let mut c = Canvas::new(2, 2).with_origin(Origin::BottomLeft);
+        let green = Color::new(0.0, 0.5, 0.0);
+        let red = Color::new(1.0, 0.0, 0.0);
+        c.write_pixel(1, 0, green);
+        let composite = c.composite_over(red);
+
+        assert_eq!(composite.origin, Origin::BottomLeft);
+        assert_eq!(composite.pixel_at(0, 0), red);
+        assert_eq!(composite.pixel_at(1, 0), green);
+        assert_eq!(composite.pixel_at(0, 1), red);
+        assert_eq!(composite.pixel_at(1, 1), red);
+    }
+
+    #[test]
+    fn a_canvas_without_a_coverage_channel_is_opaque() {
+        let c = Canvas::new(2, 2);
+
+        assert!(!c.has_alpha());
+        assert_eq!(c.alpha_at(1, 1), 1.0);
+    }
+
+    #[test]
+    fn writing_alpha_to_a_canvas() {
+        let mut c = Canvas::new(2, 2).with_origin(Origin::BottomLeft);
+        c.write_alpha(0, 1, 0.0);
+
+        assert!(c.has_alpha());
+        assert_eq!(c.alpha_at(0, 1), 0.0);
+        assert_eq!(c.alpha_at(0, 0), 1.0);
+    }
+
+    #[test]
+    fn compositing_uses_the_coverage_channel() {
+        let mut c = Canvas::new(3, 1);
+        let gray = Color::new(0.5, 0.5, 0.5);
+        let red = Color::new(1.0, 0.0, 0.0);
+        c.write_pixel(0, 0, gray);
+        c.write_alpha(1, 0, 0.0);
+        c.write_pixel(2, 0, gray);
+        c.write_alpha(2, 0, 0.5);
+        let composite = c.composite_over(red);
+
+        assert_eq!(composite.pixel_at(0, 0), gray);
+        assert_eq!(composite.pixel_at(1, 0), red);
+        assert_eq!(composite.pixel_at(2, 0), Color::new(0.75, 0.25, 0.25));
+        assert!(!composite.has_alpha());
+    }
+
+    #[test]
+    fn the_difference_of_a_canvas_with_itself_is_black() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(1, 1, Color::new(0.2, 0.4, 0.6));
+        let d = c.diff(&c, 10.0);
+
+        for y in 0..2 {
+            for x in 0..3 {
+                assert_eq!(d.pixel_at(x, y), Color::new(0.0, 0.0, 0.0));
+            }
+        }
+    }
+
+    #[test]
+    fn a_changed_pixel_shows_up_amplified_in_the_difference() {
+        let mut a = Canvas::new(3, 2);
+        let mut b = Canvas::new(3, 2);
+        a.write_pixel(2, 0, Color::new(0.5, 0.5, 0.5));
+        b.write_pixel(2, 0, Color::new(0.52, 0.5, 0.45));
+        let d = a.diff(&b, 10.0);
+
+        assert_eq!(d.pixel_at(2, 0), Color::new(0.2, 0.0, 0.5));
+        assert_eq!(d.pixel_at(1, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot diff a 3x2 canvas with a 2x3 canvas")]
+    fn diffing_canvases_of_different_sizes() {
+        Canvas::new(3, 2).diff(&Canvas::new(2, 3), 1.0);
+    }
+
+    fn from_rgbe(rgbe: &[u8]) -> Color {
+        if rgbe[3] == 0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let scale = 2f64.powi(rgbe[3] as i32 - 128 - 8);
+        Color::new(
+            rgbe[0] as f64 * scale,
+            rgbe[1] as f64 * scale,
+            rgbe[2] as f64 * scale,
+        )
+    }
+
+    #[test]
+    fn constructing_the_hdr_header() {
+        let c = Canvas::new(5, 3);
+        let hdr = c.to_hdr();
+        let header = "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 3 +X 5\n";
+
+        assert!(hdr.starts_with(header.as_bytes()));
+        assert_eq!(hdr.len(), header.len() + 5 * 3 * 4);
+    }
+
+    #[test]
+    fn bright_pixels_survive_an_hdr_round_trip() {
+        let mut c = Canvas::new(2, 1);
+        let bright = Color::new(4.0, 0.5, 1.25);
+        c.write_pixel(1, 0, bright);
+        let hdr = c.to_hdr();
+        let pixels = &hdr[hdr.len() - 8..];
+
+        assert_eq!(from_rgbe(&pixels[..4]), Color::new(0.0, 0.0, 0.0));
+        let decoded = from_rgbe(&pixels[4..]);
+        for (actual, expected) in [
+            (decoded.red, bright.red),
+            (decoded.green, bright.green),
+            (decoded.blue, bright.blue),
+        ] {
+            assert!((actual - expected).abs() <= 4.0 / 128.0);
+        }
+        assert_eq!(decoded.red, 4.0);
+    }
+
+    #[test]
+    fn out_of_range_pixels_are_clamped_in_hdr_output() {
+        let mut c = Canvas::new(3, 1);
+        c.write_pixel(0, 0, Color::new(f64::INFINITY, 1.0, 0.0));
+        c.write_pixel(1, 0, Color::new(1e40, f64::NAN, 0.0));
+        c.write_pixel(2, 0, Color::new(f64::MAX, f64::MAX, f64::MAX));
+        let hdr = c.to_hdr();
+        let pixels = &hdr[hdr.len() - 12..];
+
+        assert_eq!(&pixels[..4], &[255, 0, 0, 255]);
+        assert_eq!(&pixels[4..8], &[255, 0, 0, 255]);
+        assert_eq!(&pixels[8..], &[255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn wide_hdr_scanlines_are_run_length_encoded() {
+        let mut c = Canvas::new(8, 1);
+        c.write_pixel(3, 0, Color::new(1.0, 1.0, 1.0));
+        let hdr = c.to_hdr();
+        let header_length = "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 8\n".len();
+        let scanline = &hdr[header_length..];
+
+        assert_eq!(&scanline[..4], &[2, 2, 0, 8]);
+        assert_eq!(scanline.len(), 4 + 4 * (1 + 8));
+        assert_eq!(scanline[4], 8);
+        assert_eq!(&scanline[5..13], &[0, 0, 0, 128, 0, 0, 0, 0]);
+        assert_eq!(&scanline[31..40], &[8, 0, 0, 0, 129, 0, 0, 0, 0]);
+    }
+
     #[test]
     fn constructing_the_ppm_header() {
         let c = Canvas::new(5, 3);
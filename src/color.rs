@@ -12,6 +12,40 @@ impl Color {
     pub fn new(red: f64, green: f64, blue: f64) -> Self {
         Color { red, green, blue }
     }
+
+    // Rec. 709 weights.
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.red + 0.7152 * self.green + 0.0722 * self.blue
+    }
+
+    pub fn to_grayscale(&self) -> Color {
+        let luminance = self.luminance();
+        Color::new(luminance, luminance, luminance)
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.red.is_finite() && self.green.is_finite() && self.blue.is_finite()
+    }
+
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let component =
+            |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).unwrap() as f64 / 255.0;
+        Some(Color::new(component(0), component(2), component(4)))
+    }
+
+    pub fn to_hex(&self) -> String {
+        let component = |c: f64| (c * 255.0).clamp(0.0, 255.0).round() as u8;
+        format!(
+            "#{:02x}{:02x}{:02x}",
+            component(self.red),
+            component(self.green),
+            component(self.blue)
+        )
+    }
 }
 
 impl PartialEq for Color {
@@ -113,4 +147,52 @@ mod tests {
         let expected = Color::new(0.9, 0.2, 0.04);
         assert_eq!(c1 * c2, expected);
     }
+
+    #[test]
+    fn parsing_a_hex_color() {
+        assert_eq!(
+            Color::from_hex("#ff8800"),
+            Some(Color::new(1.0, 136.0 / 255.0, 0.0))
+        );
+        assert_eq!(Color::from_hex("ff8800"), Color::from_hex("#ff8800"));
+    }
+
+    #[test]
+    fn hex_colors_round_trip() {
+        for hex in ["#000000", "#ffffff", "#ff8800", "#1a2b3c"] {
+            assert_eq!(Color::from_hex(hex).unwrap().to_hex(), hex);
+        }
+    }
+
+    #[test]
+    fn the_luminance_of_a_color() {
+        let green = Color::new(0.0, 1.0, 0.0);
+        let blue = Color::new(0.0, 0.0, 1.0);
+
+        assert!(green.luminance() > blue.luminance());
+        assert_float_eq!(Color::new(1.0, 1.0, 1.0).luminance(), 1.0);
+    }
+
+    #[test]
+    fn converting_a_color_to_grayscale() {
+        let gray = Color::new(0.9, 0.6, 0.75).to_grayscale();
+
+        assert_float_eq!(gray.red, 0.67461);
+        assert_eq!(gray.red, gray.green);
+        assert_eq!(gray.green, gray.blue);
+    }
+
+    #[test]
+    fn converting_to_hex_clamps_the_components() {
+        assert_eq!(Color::new(1.5, -0.5, 0.5).to_hex(), "#ff0080");
+    }
+
+    #[test]
+    fn malformed_hex_colors_are_rejected() {
+        for hex in [
+            "", "#", "#fff", "#ff88001", "#gg8800", "##ff8800", "#ff 800",
+        ] {
+            assert_eq!(Color::from_hex(hex), None);
+        }
+    }
 }
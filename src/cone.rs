@@ -0,0 +1,286 @@
+use crate::bounds::Bounds;
+use crate::intersections::{Intersection, Intersections};
+use crate::material::Material;
+use crate::math::solve_quadratic;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::tuple::Tuple;
+use crate::EPSILON;
+
+// The double cone x^2 + z^2 = y^2, with its tips meeting at the origin. It can be cut off at
+// minimum and maximum along y, which are left out, and closed with caps at those ends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cone {
+    pub transform: Matrix4,
+    pub material: Material,
+    pub minimum: f64,
+    pub maximum: f64,
+    pub closed: bool,
+    pub clip: Option<(Tuple, Tuple)>,
+    pub name: Option<String>,
+}
+
+impl Cone {
+    pub fn new() -> Self {
+        Self {
+            transform: Matrix4::identity(),
+            material: Material::new(),
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
+            clip: None,
+            name: None,
+        }
+    }
+
+    // A cap at height y is a disk as wide as the cone is there.
+    fn hits_cap(local_ray: Ray, t: f64, y: f64) -> bool {
+        let x = local_ray.origin.x + t * local_ray.direction.x;
+        let z = local_ray.origin.z + t * local_ray.direction.z;
+        x.powi(2) + z.powi(2) <= y.powi(2)
+    }
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        Cone::new()
+    }
+}
+
+impl Shape for Cone {
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Matrix4 {
+        &mut self.transform
+    }
+
+    fn local_intersect(&self, local_ray: Ray) -> Intersections<'_, Self> {
+        let (o, d) = (local_ray.origin, local_ray.direction);
+        let a = d.x.powi(2) - d.y.powi(2) + d.z.powi(2);
+        let b = 2.0 * (o.x * d.x - o.y * d.y + o.z * d.z);
+        let c = o.x.powi(2) - o.y.powi(2) + o.z.powi(2);
+
+        let mut ts = Vec::with_capacity(4);
+        if a.abs() < EPSILON {
+            // Parallel to one side of the cone, so the ray crosses the other side at most once.
+            if b.abs() >= EPSILON {
+                ts.push(-c / (2.0 * b));
+            }
+        } else if let Some((t0, t1)) = solve_quadratic(a, b, c) {
+            ts.extend([t0, t1]);
+        }
+        ts.retain(|&t| {
+            let y = o.y + t * d.y;
+            self.minimum < y && y < self.maximum
+        });
+
+        if self.closed && d.y.abs() >= EPSILON {
+            for y in [self.minimum, self.maximum] {
+                let t = (y - o.y) / d.y;
+                if Cone::hits_cap(local_ray, t, y) {
+                    ts.push(t);
+                }
+            }
+        }
+        Intersections::new_unsorted(ts.into_iter().map(|t| Intersection::new(t, self)).collect())
+    }
+
+    fn local_normal_at(&self, local_point: Tuple) -> Tuple {
+        let dist = local_point.x.powi(2) + local_point.z.powi(2);
+        if dist < self.maximum.powi(2) && local_point.y >= self.maximum - EPSILON {
+            return Tuple::new_vector(0.0, 1.0, 0.0);
+        }
+        if dist < self.minimum.powi(2) && local_point.y <= self.minimum + EPSILON {
+            return Tuple::new_vector(0.0, -1.0, 0.0);
+        }
+        // The sides meet at the tip, which has no normal of its own, so it gets the axis.
+        if dist < EPSILON.powi(2) && local_point.y.abs() < EPSILON {
+            return Tuple::new_vector(0.0, 1.0, 0.0);
+        }
+        let y = if local_point.y > 0.0 {
+            -dist.sqrt()
+        } else {
+            dist.sqrt()
+        };
+        Tuple::new_vector(local_point.x, y, local_point.z)
+    }
+
+    // The book's side normals aren't normalized; normal_at does that.
+    fn local_normals_are_unit(&self) -> bool {
+        false
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn set_name(&mut self, name: impl Into<String>) {
+        self.name = Some(name.into());
+    }
+
+    fn clip(&self) -> Option<(Tuple, Tuple)> {
+        self.clip
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        let limit = self.minimum.abs().max(self.maximum.abs());
+        Bounds::new(
+            Tuple::new_point(-limit, self.minimum, -limit),
+            Tuple::new_point(limit, self.maximum, limit),
+        )
+    }
+
+    fn max_intersections(&self) -> usize {
+        4
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cone::Cone;
+    use crate::ray::Ray;
+    use crate::shape::Shape;
+    use crate::tuple::Tuple;
+    use crate::world::{default_world, World};
+
+    #[test]
+    fn the_default_minimum_and_maximum_for_a_cone() {
+        let cone = Cone::new();
+
+        assert_eq!(cone.minimum, f64::NEG_INFINITY);
+        assert_eq!(cone.maximum, f64::INFINITY);
+        assert!(!cone.closed);
+    }
+
+    #[test]
+    fn intersecting_a_cone_with_a_ray() {
+        let shape = Cone::new();
+        let examples = [
+            ((0.0, 0.0, -5.0), (0.0, 0.0, 1.0), 5.0, 5.0),
+            ((0.0, 0.0, -5.0), (1.0, 1.0, 1.0), 8.66025, 8.66025),
+            ((1.0, 1.0, -5.0), (-0.5, -1.0, 1.0), 4.55006, 49.44994),
+        ];
+
+        for ((ox, oy, oz), (dx, dy, dz), t0, t1) in examples {
+            let direction = Tuple::new_vector(dx, dy, dz).normalize();
+            let r = Ray::new(Tuple::new_point(ox, oy, oz), direction);
+            let xs = shape.local_intersect(r);
+            assert_eq!(xs.len(), 2);
+            assert!((xs[0].t - t0).abs() < 0.0001);
+            assert!((xs[1].t - t1).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn intersecting_a_cone_with_a_ray_parallel_to_one_of_its_halves() {
+        let shape = Cone::new();
+        let direction = Tuple::new_vector(0.0, 1.0, 1.0).normalize();
+        let r = Ray::new(Tuple::new_point(0.0, 0.0, -1.0), direction);
+        let xs = shape.local_intersect(r);
+
+        assert_eq!(xs.len(), 1);
+        assert!((xs[0].t - 0.35355).abs() < 0.0001);
+    }
+
+    #[test]
+    fn intersecting_a_cones_end_caps() {
+        let mut shape = Cone::new();
+        shape.minimum = -0.5;
+        shape.maximum = 0.5;
+        shape.closed = true;
+        let examples = [
+            ((0.0, 0.0, -5.0), (0.0, 1.0, 0.0), 0),
+            ((0.0, 0.0, -0.25), (0.0, 1.0, 1.0), 2),
+            ((0.0, 0.0, -0.25), (0.0, 1.0, 0.0), 4),
+        ];
+
+        for ((ox, oy, oz), (dx, dy, dz), count) in examples {
+            let direction = Tuple::new_vector(dx, dy, dz).normalize();
+            let r = Ray::new(Tuple::new_point(ox, oy, oz), direction);
+            assert_eq!(shape.local_intersect(r).len(), count);
+        }
+    }
+
+    #[test]
+    fn computing_the_normal_vector_on_a_cone() {
+        let shape = Cone::new();
+        let examples = [
+            ((0.0, 0.0, 0.0), (0.0, 1.0, 0.0)),
+            ((1.0, 1.0, 1.0), (1.0, -f64::sqrt(2.0), 1.0)),
+            ((-1.0, -1.0, 0.0), (-1.0, 1.0, 0.0)),
+        ];
+
+        for ((px, py, pz), (nx, ny, nz)) in examples {
+            let normal = shape.local_normal_at(Tuple::new_point(px, py, pz));
+            assert_eq!(normal, Tuple::new_vector(nx, ny, nz));
+        }
+    }
+
+    #[test]
+    fn shading_a_ray_that_hits_the_tip_of_a_cone() {
+        let mut w: World = World::new();
+        w.light = default_world().light;
+        w.objects.push(Cone::new().into());
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = w.intersect_world(r);
+        let hit = xs.hit().unwrap();
+
+        assert!((hit.t - 5.0).abs() < 0.0001);
+        assert_eq!(
+            hit.normal_at(r.position(hit.t)),
+            Tuple::new_vector(0.0, 1.0, 0.0)
+        );
+        assert!(w.color_at(r).red.is_finite());
+    }
+
+    #[test]
+    fn the_normal_on_a_cones_end_caps() {
+        let mut shape = Cone::new();
+        shape.minimum = -1.0;
+        shape.maximum = 2.0;
+        shape.closed = true;
+
+        assert_eq!(
+            shape.local_normal_at(Tuple::new_point(0.5, 2.0, 0.5)),
+            Tuple::new_vector(0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            shape.local_normal_at(Tuple::new_point(0.5, -1.0, 0.0)),
+            Tuple::new_vector(0.0, -1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn a_cone_reports_at_most_four_intersections() {
+        let shape = Cone::new();
+
+        assert_eq!(shape.max_intersections(), 4);
+    }
+
+    #[test]
+    fn the_bounds_of_a_cone() {
+        let mut shape = Cone::new();
+        assert_eq!(shape.local_bounds().minimum.y, f64::NEG_INFINITY);
+        assert_eq!(shape.local_bounds().maximum.x, f64::INFINITY);
+
+        shape.minimum = -5.0;
+        shape.maximum = 3.0;
+        let bounds = shape.local_bounds();
+        assert_eq!(bounds.minimum, Tuple::new_point(-5.0, -5.0, -5.0));
+        assert_eq!(bounds.maximum, Tuple::new_point(5.0, 3.0, 5.0));
+    }
+}
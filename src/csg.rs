@@ -0,0 +1,248 @@
+use crate::bounds::Bounds;
+use crate::intersections::Intersections;
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::world::WorldShape;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Operation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+// Whether a hit on the left shape (or the right one if lhit is false) is on the surface of the
+// combined shape, given whether the ray is inside the left and right shapes at that point.
+pub fn intersection_allowed(operation: Operation, lhit: bool, inl: bool, inr: bool) -> bool {
+    match operation {
+        Operation::Union => (lhit && !inr) || (!lhit && !inl),
+        Operation::Intersection => (lhit && inr) || (!lhit && inl),
+        Operation::Difference => (lhit && !inr) || (!lhit && inl),
+    }
+}
+
+// Two shapes combined by an operation. Like a group it has no surface of its own, hits land on
+// the shapes inside it, and it is only a Shape as a WorldShape.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Csg {
+    pub operation: Operation,
+    pub left: Box<WorldShape>,
+    pub right: Box<WorldShape>,
+    pub transform: Matrix4,
+    pub material: Material,
+    pub name: Option<String>,
+}
+
+impl Csg {
+    pub fn new(
+        operation: Operation,
+        left: impl Into<WorldShape>,
+        right: impl Into<WorldShape>,
+    ) -> Self {
+        Self {
+            operation,
+            left: Box::new(left.into()),
+            right: Box::new(right.into()),
+            transform: Matrix4::identity(),
+            material: Material::new(),
+            name: None,
+        }
+    }
+
+    pub fn filter_intersections<'a>(
+        &self,
+        xs: Intersections<'a, WorldShape>,
+    ) -> Intersections<'a, WorldShape> {
+        let mut inl = false;
+        let mut inr = false;
+        let mut result = Vec::new();
+        for x in xs.iter() {
+            let lhit = self.left.includes(x.object);
+            if intersection_allowed(self.operation, lhit, inl, inr) {
+                result.push(*x);
+            }
+            if lhit {
+                inl = !inl;
+            } else {
+                inr = !inr;
+            }
+        }
+        Intersections::new(result)
+    }
+
+    // The intersections of a ray in the shape's space that are on its surface, with the shapes
+    // inside it as objects. Like a group's, their parent transforms take in its own transform.
+    // Coincident intersections are merged first, so shapes that touch don't leave a seam.
+    pub fn intersect_children(&self, local_ray: Ray) -> Intersections<'_, WorldShape> {
+        let mut xs = self.left.intersect(local_ray).into_vec();
+        xs.extend(self.right.intersect(local_ray).into_vec());
+        let mut xs = Intersections::new_unsorted(xs);
+        xs.merge_coincident(local_ray);
+        self.filter_intersections(xs).within(self.transform)
+    }
+
+    pub fn local_bounds(&self) -> Bounds {
+        let mut bounds = self.left.parent_space_bounds();
+        bounds.merge(&self.right.parent_space_bounds());
+        bounds
+    }
+
+    pub fn max_intersections(&self) -> usize {
+        self.left.max_intersections() + self.right.max_intersections()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::csg::{intersection_allowed, Csg, Operation};
+    use crate::cube::Cube;
+    use crate::intersections::{Intersection, Intersections};
+    use crate::matrix::Matrix4;
+    use crate::ray::Ray;
+    use crate::shape::Shape;
+    use crate::sphere::Sphere;
+    use crate::tuple::Tuple;
+    use crate::world::WorldShape;
+
+    #[test]
+    fn csg_is_created_with_an_operation_and_two_shapes() {
+        let s1 = Sphere::new();
+        let s2 = Cube::new();
+        let c = Csg::new(Operation::Union, s1.clone(), s2.clone());
+
+        assert_eq!(c.operation, Operation::Union);
+        assert_eq!(*c.left, WorldShape::from(s1));
+        assert_eq!(*c.right, WorldShape::from(s2));
+    }
+
+    #[test]
+    fn evaluating_the_rule_for_a_csg_operation() {
+        let examples = [
+            (Operation::Union, true, true, true, false),
+            (Operation::Union, true, true, false, true),
+            (Operation::Union, true, false, true, false),
+            (Operation::Union, true, false, false, true),
+            (Operation::Union, false, true, true, false),
+            (Operation::Union, false, true, false, false),
+            (Operation::Union, false, false, true, true),
+            (Operation::Union, false, false, false, true),
+            (Operation::Intersection, true, true, true, true),
+            (Operation::Intersection, true, true, false, false),
+            (Operation::Intersection, true, false, true, true),
+            (Operation::Intersection, true, false, false, false),
+            (Operation::Intersection, false, true, true, true),
+            (Operation::Intersection, false, true, false, true),
+            (Operation::Intersection, false, false, true, false),
+            (Operation::Intersection, false, false, false, false),
+            (Operation::Difference, true, true, true, false),
+            (Operation::Difference, true, true, false, true),
+            (Operation::Difference, true, false, true, false),
+            (Operation::Difference, true, false, false, true),
+            (Operation::Difference, false, true, true, true),
+            (Operation::Difference, false, true, false, true),
+            (Operation::Difference, false, false, true, false),
+            (Operation::Difference, false, false, false, false),
+        ];
+
+        for (operation, lhit, inl, inr, result) in examples {
+            assert_eq!(intersection_allowed(operation, lhit, inl, inr), result);
+        }
+    }
+
+    #[test]
+    fn filtering_a_list_of_intersections() {
+        let examples = [
+            (Operation::Union, 0, 3),
+            (Operation::Intersection, 1, 2),
+            (Operation::Difference, 0, 1),
+        ];
+
+        for (operation, x0, x1) in examples {
+            let c = Csg::new(operation, Sphere::new(), Cube::new());
+            let xs = vec![
+                Intersection::new(1.0, &*c.left),
+                Intersection::new(2.0, &*c.right),
+                Intersection::new(3.0, &*c.left),
+                Intersection::new(4.0, &*c.right),
+            ];
+            let result = c.filter_intersections(Intersections::new(xs.clone()));
+
+            assert_eq!(result.len(), 2);
+            assert_eq!(result[0], xs[x0]);
+            assert_eq!(result[1], xs[x1]);
+        }
+    }
+
+    #[test]
+    fn a_ray_misses_a_csg_object() {
+        let c = Csg::new(Operation::Union, Sphere::new(), Cube::new());
+        let r = Ray::new(
+            Tuple::new_point(0.0, 2.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+
+        assert!(c.intersect_children(r).is_empty());
+    }
+
+    #[test]
+    fn a_ray_hits_a_csg_object() {
+        let s1 = Sphere::new();
+        let mut s2 = Sphere::new();
+        s2.transform = Matrix4::identity().translate(0.0, 0.0, 0.5);
+        let c = Csg::new(Operation::Union, s1, s2);
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = c.intersect_children(r);
+
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4.0);
+        assert_eq!(xs[0].object, &*c.left);
+        assert_eq!(xs[1].t, 6.5);
+        assert_eq!(xs[1].object, &*c.right);
+    }
+
+    #[test]
+    fn the_union_of_two_touching_spheres_has_no_seam() {
+        let mut left = Sphere::new();
+        left.transform = Matrix4::translation(-1.0, 0.0, 0.0);
+        let mut right = Sphere::new();
+        right.transform = Matrix4::translation(1.0, 0.0, 0.0);
+        let c = Csg::new(Operation::Union, left, right);
+
+        for i in 0..100 {
+            let offset = (i as f64 - 50.0) * 1e-5;
+            let r = Ray::new(
+                Tuple::new_point(-5.0, offset, offset / 3.0),
+                Tuple::new_vector(1.0, 0.0, 0.0),
+            );
+            let xs = c.intersect_children(r);
+
+            assert_eq!(xs.len(), 2);
+            assert!(std::ptr::eq(xs[0].object, &*c.left));
+            assert!(std::ptr::eq(xs[1].object, &*c.right));
+        }
+    }
+
+    #[test]
+    fn hits_on_a_transformed_csg_object_carry_its_transform() {
+        let mut c = Csg::new(Operation::Union, Sphere::new(), Cube::new());
+        c.transform = Matrix4::identity().translate(1.0, 0.0, 0.0);
+        let r = Ray::new(
+            Tuple::new_point(1.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let shape = WorldShape::from(c.clone());
+        let xs = shape.intersect(r);
+
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].parent_transform, c.transform);
+        assert_eq!(
+            xs[0].normal_at(Tuple::new_point(1.0, 0.0, -1.0)),
+            Tuple::new_vector(0.0, 0.0, -1.0)
+        );
+    }
+}
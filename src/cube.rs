@@ -0,0 +1,195 @@
+use crate::bounds::Bounds;
+use crate::intersections::{Intersection, Intersections};
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::tuple::Tuple;
+use crate::EPSILON;
+
+// An axis-aligned cube from -1 to 1 on every axis.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Cube {
+    pub transform: Matrix4,
+    pub material: Material,
+    pub clip: Option<(Tuple, Tuple)>,
+    pub name: Option<String>,
+}
+
+impl Cube {
+    pub fn new() -> Self {
+        Self {
+            transform: Matrix4::identity(),
+            material: Material::new(),
+            clip: None,
+            name: None,
+        }
+    }
+}
+
+// Where the ray enters and leaves the slab between -1 and 1 on one axis.
+fn check_axis(origin: f64, direction: f64) -> (f64, f64) {
+    let tmin_numerator = -1.0 - origin;
+    let tmax_numerator = 1.0 - origin;
+    let (tmin, tmax) = if direction.abs() >= EPSILON {
+        (tmin_numerator / direction, tmax_numerator / direction)
+    } else {
+        (
+            tmin_numerator * f64::INFINITY,
+            tmax_numerator * f64::INFINITY,
+        )
+    };
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+impl Shape for Cube {
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Matrix4 {
+        &mut self.transform
+    }
+
+    fn local_intersect(&self, local_ray: Ray) -> Intersections<'_, Self> {
+        let (xtmin, xtmax) = check_axis(local_ray.origin.x, local_ray.direction.x);
+        let (ytmin, ytmax) = check_axis(local_ray.origin.y, local_ray.direction.y);
+        let (ztmin, ztmax) = check_axis(local_ray.origin.z, local_ray.direction.z);
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+        if tmin > tmax {
+            return Intersections::new_unsorted(Vec::new());
+        }
+        Intersections::new_unsorted(vec![
+            Intersection::new(tmin, self),
+            Intersection::new(tmax, self),
+        ])
+    }
+
+    fn local_normal_at(&self, local_point: Tuple) -> Tuple {
+        let (x, y, z) = (
+            local_point.x.abs(),
+            local_point.y.abs(),
+            local_point.z.abs(),
+        );
+        let maxc = x.max(y).max(z);
+        if maxc == x {
+            Tuple::new_vector(local_point.x, 0.0, 0.0)
+        } else if maxc == y {
+            Tuple::new_vector(0.0, local_point.y, 0.0)
+        } else {
+            Tuple::new_vector(0.0, 0.0, local_point.z)
+        }
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn set_name(&mut self, name: impl Into<String>) {
+        self.name = Some(name.into());
+    }
+
+    fn clip(&self) -> Option<(Tuple, Tuple)> {
+        self.clip
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::new(
+            Tuple::new_point(-1.0, -1.0, -1.0),
+            Tuple::new_point(1.0, 1.0, 1.0),
+        )
+    }
+
+    fn max_intersections(&self) -> usize {
+        2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_float_eq;
+    use crate::cube::Cube;
+    use crate::ray::Ray;
+    use crate::shape::Shape;
+    use crate::tuple::Tuple;
+
+    #[test]
+    fn a_ray_intersects_a_cube() {
+        let c = Cube::new();
+        let examples = [
+            ((5.0, 0.5, 0.0), (-1.0, 0.0, 0.0), 4.0, 6.0),
+            ((-5.0, 0.5, 0.0), (1.0, 0.0, 0.0), 4.0, 6.0),
+            ((0.5, 5.0, 0.0), (0.0, -1.0, 0.0), 4.0, 6.0),
+            ((0.5, -5.0, 0.0), (0.0, 1.0, 0.0), 4.0, 6.0),
+            ((0.5, 0.0, 5.0), (0.0, 0.0, -1.0), 4.0, 6.0),
+            ((0.5, 0.0, -5.0), (0.0, 0.0, 1.0), 4.0, 6.0),
+            ((0.0, 0.5, 0.0), (0.0, 0.0, 1.0), -1.0, 1.0),
+        ];
+
+        for ((ox, oy, oz), (dx, dy, dz), t1, t2) in examples {
+            let r = Ray::new(Tuple::new_point(ox, oy, oz), Tuple::new_vector(dx, dy, dz));
+            let xs = c.local_intersect(r);
+            assert_eq!(xs.len(), 2);
+            assert_float_eq!(xs[0].t, t1);
+            assert_float_eq!(xs[1].t, t2);
+        }
+    }
+
+    #[test]
+    fn a_ray_misses_a_cube() {
+        let c = Cube::new();
+        let examples = [
+            ((-2.0, 0.0, 0.0), (0.2673, 0.5345, 0.8018)),
+            ((0.0, -2.0, 0.0), (0.8018, 0.2673, 0.5345)),
+            ((0.0, 0.0, -2.0), (0.5345, 0.8018, 0.2673)),
+            ((2.0, 0.0, 2.0), (0.0, 0.0, -1.0)),
+            ((0.0, 2.0, 2.0), (0.0, -1.0, 0.0)),
+            ((2.0, 2.0, 0.0), (-1.0, 0.0, 0.0)),
+        ];
+
+        for ((ox, oy, oz), (dx, dy, dz)) in examples {
+            let r = Ray::new(Tuple::new_point(ox, oy, oz), Tuple::new_vector(dx, dy, dz));
+            assert!(c.local_intersect(r).is_empty());
+        }
+    }
+
+    #[test]
+    fn the_normal_on_the_surface_of_a_cube() {
+        let c = Cube::new();
+        let examples = [
+            ((1.0, 0.5, -0.8), (1.0, 0.0, 0.0)),
+            ((-1.0, -0.2, 0.9), (-1.0, 0.0, 0.0)),
+            ((-0.4, 1.0, -0.1), (0.0, 1.0, 0.0)),
+            ((0.3, -1.0, -0.7), (0.0, -1.0, 0.0)),
+            ((-0.6, 0.3, 1.0), (0.0, 0.0, 1.0)),
+            ((0.4, 0.4, -1.0), (0.0, 0.0, -1.0)),
+            ((1.0, 1.0, 1.0), (1.0, 0.0, 0.0)),
+            ((-1.0, -1.0, -1.0), (-1.0, 0.0, 0.0)),
+        ];
+
+        for ((px, py, pz), (nx, ny, nz)) in examples {
+            let normal = c.local_normal_at(Tuple::new_point(px, py, pz));
+            assert_eq!(normal, Tuple::new_vector(nx, ny, nz));
+        }
+    }
+
+    #[test]
+    fn a_cube_reports_at_most_two_intersections() {
+        let c = Cube::new();
+
+        assert_eq!(c.max_intersections(), 2);
+    }
+}
@@ -0,0 +1,442 @@
+use crate::bounds::Bounds;
+use crate::intersections::{BoundedShapes, Intersections};
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::world::WorldShape;
+
+// A collection of shapes transformed together. It has no surface of its own, so its material is
+// never used; hits always land on one of the children. It is not a Shape itself, since a hit on it
+// could only report the group; it goes into a world or another group as a WorldShape.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Group {
+    pub transform: Matrix4,
+    pub material: Material,
+    pub name: Option<String>,
+    children: BoundedShapes<WorldShape>,
+}
+
+impl Group {
+    pub fn new() -> Self {
+        Self {
+            transform: Matrix4::identity(),
+            material: Material::new(),
+            name: None,
+            children: BoundedShapes::new(),
+        }
+    }
+
+    pub fn add_child(&mut self, child: impl Into<WorldShape>) {
+        self.children.push(child.into());
+    }
+
+    // Children can only be changed through here, so the group's boxes always fit them.
+    pub fn update_child(&mut self, index: usize, f: impl FnOnce(&mut WorldShape)) {
+        self.children.update(index, f);
+    }
+
+    // Gives the material to every shape in the group, however deeply nested, such as all the
+    // triangles of an imported mesh.
+    pub fn set_material_recursive(&mut self, material: Material) {
+        self.children
+            .update_all(|child| set_material_within(child, &material));
+        self.material = material;
+    }
+
+    pub fn children(&self) -> &[WorldShape] {
+        self.children.shapes()
+    }
+
+    // The intersections of a ray in group space with every child, with the children as objects.
+    // Their parent transforms take in the group's own transform.
+    pub fn intersect_children(&self, local_ray: Ray) -> Intersections<'_, WorldShape> {
+        self.children.intersect(local_ray).within(self.transform)
+    }
+
+    // Kept up to date as children are added or changed, so it costs nothing to ask for.
+    pub fn local_bounds(&self) -> Bounds {
+        self.children.bounds()
+    }
+
+    // Also kept up to date, so sizing the intersections of a ray doesn't walk the children.
+    pub fn max_intersections(&self) -> usize {
+        self.children.max_intersections()
+    }
+
+    // Whether any shape in the group casts a shadow, kept up to date the same way.
+    pub fn casts_shadow(&self) -> bool {
+        self.children.casts_shadow()
+    }
+}
+
+fn set_material_within(shape: &mut WorldShape, material: &Material) {
+    match shape {
+        WorldShape::Group(group) => group.set_material_recursive(material.clone()),
+        WorldShape::Csg(csg) => {
+            set_material_within(&mut csg.left, material);
+            set_material_within(&mut csg.right, material);
+            csg.material = material.clone();
+        }
+        _ => shape.set_material(material.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::csg::{Csg, Operation};
+    use crate::cube::Cube;
+    use crate::group::Group;
+    use crate::intersections::Intersection;
+    use crate::light::PointLight;
+    use crate::material::Material;
+    use crate::matrix::Matrix4;
+    use crate::plane::Plane;
+    use crate::ray::Ray;
+    use crate::shape::Shape;
+    use crate::sphere::Sphere;
+    use crate::triangle::Triangle;
+    use crate::tuple::Tuple;
+    use crate::world::{World, WorldShape};
+    use std::f64::consts::PI;
+
+    #[test]
+    fn creating_a_new_group() {
+        let g = Group::new();
+
+        assert_eq!(g.transform, Matrix4::identity());
+        assert!(g.children().is_empty());
+    }
+
+    #[test]
+    fn adding_a_child_to_a_group() {
+        let mut g = Group::new();
+        g.transform = Matrix4::identity().translate(1.0, 0.0, 0.0);
+        let s = Sphere::new();
+        g.add_child(s.clone());
+
+        assert_eq!(g.children().len(), 1);
+        assert_eq!(g.children()[0].transform(), s.transform());
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_an_empty_group() {
+        let g = Group::new();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+
+        assert!(g.intersect_children(r).is_empty());
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_nonempty_group() {
+        let mut g = Group::new();
+        let s1 = Sphere::new();
+        let mut s2 = Sphere::new();
+        s2.transform = Matrix4::identity().translate(0.0, 0.0, -3.0);
+        let mut s3 = Sphere::new();
+        s3.transform = Matrix4::identity().translate(5.0, 0.0, 0.0);
+        g.add_child(s1);
+        g.add_child(s2);
+        g.add_child(s3);
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = g.intersect_children(r);
+
+        assert_eq!(xs.len(), 4);
+        assert_eq!(xs[0].object, &g.children()[1]);
+        assert_eq!(xs[1].object, &g.children()[1]);
+        assert_eq!(xs[2].object, &g.children()[0]);
+        assert_eq!(xs[3].object, &g.children()[0]);
+    }
+
+    #[test]
+    fn intersecting_a_transformed_group() {
+        let mut g = Group::new();
+        g.transform = Matrix4::identity().scale(2.0, 2.0, 2.0);
+        let mut s = Sphere::new();
+        s.transform = Matrix4::identity().translate(5.0, 0.0, 0.0);
+        g.add_child(s);
+        let r = Ray::new(
+            Tuple::new_point(10.0, 0.0, -10.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+
+        assert_eq!(WorldShape::from(g).intersect(r).len(), 2);
+    }
+
+    #[test]
+    fn a_group_in_the_world_reports_its_children_as_hit_objects() {
+        let mut g = Group::new();
+        g.transform = Matrix4::identity().scale(2.0, 2.0, 2.0);
+        g.add_child(Sphere::new());
+        let shape = WorldShape::from(g);
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = shape.intersect(r);
+
+        let WorldShape::Group(g) = &shape else {
+            unreachable!()
+        };
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 3.0);
+        assert_eq!(xs[1].t, 7.0);
+        assert_eq!(xs[0].object, &g.children()[0]);
+        assert_eq!(shape.max_intersections(), 2);
+    }
+
+    fn nested_groups() -> Group {
+        let mut g1 = Group::new();
+        g1.transform = Matrix4::rotation_y(PI / 2.0);
+        let mut g2 = Group::new();
+        g2.transform = Matrix4::identity().scale(2.0, 2.0, 2.0);
+        let mut s = Sphere::new();
+        s.transform = Matrix4::identity().translate(5.0, 0.0, 0.0);
+        g2.add_child(s);
+        g1.add_child(g2);
+        g1
+    }
+
+    #[test]
+    fn intersections_carry_the_transforms_of_the_groups_above_the_object() {
+        let shape = WorldShape::from(nested_groups());
+        let WorldShape::Group(g1) = &shape else {
+            unreachable!()
+        };
+        let WorldShape::Group(g2) = &g1.children()[0] else {
+            unreachable!()
+        };
+        let r = Ray::new(
+            Tuple::new_point(-5.0, 0.0, -10.0),
+            Tuple::new_vector(1.0, 0.0, 0.0),
+        );
+        let xs = shape.intersect(r);
+
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].object, &g2.children()[0]);
+        assert_eq!(xs[0].parent_transform, g1.transform * g2.transform);
+    }
+
+    #[test]
+    fn converting_a_point_from_world_to_object_space() {
+        let shape = WorldShape::from(nested_groups());
+        let r = Ray::new(
+            Tuple::new_point(-5.0, 0.0, -10.0),
+            Tuple::new_vector(1.0, 0.0, 0.0),
+        );
+        let xs = shape.intersect(r);
+        let point = r.position(xs[0].t);
+        let p = xs[0]
+            .object
+            .world_to_object(xs[0].parent_transform.inverse() * point);
+
+        assert_eq!(point, Tuple::new_point(-2.0, 0.0, -10.0));
+        assert_eq!(p, Tuple::new_point(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn finding_the_normal_on_a_child_object() {
+        let g1 = Matrix4::rotation_y(PI / 2.0);
+        let g2 = Matrix4::identity().scale(1.0, 2.0, 3.0);
+        let mut s = Sphere::new();
+        s.transform = Matrix4::identity().translate(5.0, 0.0, 0.0);
+        let x = Intersection::new(0.0, &s).within(g2).within(g1);
+        let n = x.normal_at(Tuple::new_point(1.7321, 1.1547, -5.5774));
+
+        assert!((n.x - 0.2857).abs() < 0.0001);
+        assert!((n.y - 0.4286).abs() < 0.0001);
+        assert!((n.z + 0.8571).abs() < 0.0001);
+    }
+
+    // The normal on the sphere at (3, 0, -1) in the world, once the group has been moved there
+    // by any of the ways a transform can be changed after the sphere went into the group.
+    fn normal_after_moving_group(move_group: impl Fn(&mut World<WorldShape>)) -> Tuple {
+        let mut w = World::new();
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        w.objects.push(g.into());
+        move_group(&mut w);
+        let r = Ray::new(
+            Tuple::new_point(3.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = w.intersect_world(r);
+        let hit = xs.hit().unwrap();
+        hit.normal_at(r.position(hit.t))
+    }
+
+    #[test]
+    fn changing_a_group_transform_after_adding_children_moves_their_normals() {
+        let moves: [fn(&mut World<WorldShape>); 3] = [
+            |w| {
+                let WorldShape::Group(g) = &mut w.objects[0] else {
+                    unreachable!()
+                };
+                g.transform = Matrix4::translation(3.0, 0.0, 0.0);
+            },
+            |w| w.objects[0].set_transform(Matrix4::translation(3.0, 0.0, 0.0)),
+            |w| w.apply_transform(Matrix4::translation(3.0, 0.0, 0.0)),
+        ];
+
+        for move_group in moves {
+            assert_eq!(
+                normal_after_moving_group(move_group),
+                Tuple::new_vector(0.0, 0.0, -1.0)
+            );
+        }
+    }
+
+    #[test]
+    fn converting_handedness_flips_the_normals_of_grouped_shapes() {
+        let mut w = World::new();
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        g.transform = Matrix4::translation(0.0, 0.0, 5.0);
+        w.objects.push(WorldShape::from(g));
+        w.convert_handedness();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 0.0, -1.0),
+        );
+        let xs = w.intersect_world(r);
+        let hit = xs.hit().unwrap();
+
+        assert_eq!(r.position(hit.t), Tuple::new_point(0.0, 0.0, -4.0));
+        assert_eq!(
+            hit.normal_at(r.position(hit.t)),
+            Tuple::new_vector(0.0, 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn a_group_has_a_bounding_box_that_contains_its_children() {
+        let mut s = Sphere::new();
+        s.transform = Matrix4::identity()
+            .scale(2.0, 2.0, 2.0)
+            .translate(2.0, 5.0, -3.0);
+        let mut c = Cube::new();
+        c.transform = Matrix4::identity().translate(-4.0, -1.0, 4.0);
+        let mut g = Group::new();
+        g.add_child(s);
+        g.add_child(c);
+        let bounds = g.local_bounds();
+
+        assert_eq!(bounds.minimum, Tuple::new_point(-5.0, -2.0, -5.0));
+        assert_eq!(bounds.maximum, Tuple::new_point(4.0, 7.0, 5.0));
+    }
+
+    #[test]
+    fn moving_a_child_moves_the_group_bounds() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        g.update_child(0, |s| s.set_transform(Matrix4::translation(0.0, 5.0, 0.0)));
+        let r = Ray::new(
+            Tuple::new_point(0.0, 5.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+
+        assert_eq!(g.local_bounds().minimum, Tuple::new_point(-1.0, 4.0, -1.0));
+        assert_eq!(g.local_bounds().maximum, Tuple::new_point(1.0, 6.0, 1.0));
+        assert_eq!(g.intersect_children(r).len(), 2);
+    }
+
+    #[test]
+    fn a_group_keeps_count_of_the_intersections_its_children_can_report() {
+        let mut inner = Group::new();
+        inner.add_child(Sphere::new());
+        inner.add_child(Cube::new());
+        let mut g = Group::new();
+        g.add_child(inner);
+        g.add_child(Sphere::new());
+
+        assert_eq!(g.max_intersections(), 6);
+
+        g.update_child(1, |s| *s = Plane::new().into());
+
+        assert_eq!(g.max_intersections(), 5);
+    }
+
+    #[test]
+    fn a_group_keeps_track_of_whether_anything_in_it_casts_a_shadow() {
+        let mut s = Sphere::new();
+        s.material.cast_shadow = false;
+        let mut inner = Group::new();
+        inner.add_child(s);
+        let mut g = Group::new();
+        g.add_child(inner);
+
+        assert!(!Group::new().casts_shadow());
+        assert!(!g.casts_shadow());
+
+        g.update_child(0, |child| {
+            if let WorldShape::Group(inner) = child {
+                inner.add_child(Cube::new());
+            }
+        });
+
+        assert!(g.casts_shadow());
+    }
+
+    #[test]
+    fn setting_a_material_on_every_shape_in_nested_groups() {
+        let t = Triangle::new(
+            Tuple::new_point(0.0, 1.0, 0.0),
+            Tuple::new_point(-1.0, 0.0, 0.0),
+            Tuple::new_point(1.0, 0.0, 0.0),
+        );
+        let mut inner = Group::new();
+        inner.add_child(t);
+        inner.add_child(Csg::new(Operation::Union, Sphere::new(), Cube::new()));
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        g.add_child(inner);
+        let mut m = Material::new();
+        m.color = Color::new(1.0, 0.2, 0.2);
+        g.set_material_recursive(m.clone());
+
+        assert_eq!(*g.children()[0].material(), m);
+        let WorldShape::Group(inner) = &g.children()[1] else {
+            unreachable!()
+        };
+        assert!(matches!(inner.children()[0], WorldShape::Triangle(_)));
+        assert_eq!(*inner.children()[0].material(), m);
+        let WorldShape::Csg(csg) = &inner.children()[1] else {
+            unreachable!()
+        };
+        assert_eq!(*csg.left.material(), m);
+        assert_eq!(*csg.right.material(), m);
+    }
+
+    #[test]
+    fn an_empty_group_has_empty_bounds() {
+        let g = Group::new();
+
+        assert!(g.local_bounds().is_empty());
+        assert!(WorldShape::from(g).parent_space_bounds().is_empty());
+    }
+
+    #[test]
+    fn shading_a_hit_on_a_shape_inside_a_group() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        let mut w: World = World::new();
+        w.light = Some(PointLight::new(
+            Tuple::new_point(-10.0, 10.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        w.objects.push(g.into());
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+
+        assert_ne!(w.color_at(r), Color::new(0.0, 0.0, 0.0));
+    }
+}
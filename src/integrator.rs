@@ -0,0 +1,141 @@
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::random::XorShift;
+use crate::shape::Shape;
+use crate::world::{Emitter, PathTermination, World};
+
+// Renders hand every pixel the world's emitters, found once per render, for integrators that
+// sample them directly.
+pub trait PixelIntegrator {
+    fn integrate<S: Shape>(
+        &self,
+        camera: &Camera,
+        world: &World<S>,
+        emitters: &[Emitter<S>],
+        px: usize,
+        py: usize,
+    ) -> Color;
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct SingleSample;
+
+impl PixelIntegrator for SingleSample {
+    fn integrate<S: Shape>(
+        &self,
+        camera: &Camera,
+        world: &World<S>,
+        _emitters: &[Emitter<S>],
+        px: usize,
+        py: usize,
+    ) -> Color {
+        world.color_at(camera.ray_for_pixel(px, py))
+    }
+}
+
+// Casts an n x n grid of rays through the sub-pixel cell centers and averages them.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoxFilterAA(pub usize);
+
+impl PixelIntegrator for BoxFilterAA {
+    fn integrate<S: Shape>(
+        &self,
+        camera: &Camera,
+        world: &World<S>,
+        _emitters: &[Emitter<S>],
+        px: usize,
+        py: usize,
+    ) -> Color {
+        let n = self.0.max(1);
+        let mut sum = Color::new(0.0, 0.0, 0.0);
+        for j in 0..n {
+            for i in 0..n {
+                let dx = (i as f64 + 0.5) / n as f64;
+                let dy = (j as f64 + 0.5) / n as f64;
+                sum = sum + world.color_at(camera.ray_for_subpixel(px, py, dx, dy));
+            }
+        }
+        sum * (1.0 / (n * n) as f64)
+    }
+}
+
+// Averages samples path traced rays through random points in the pixel. Each pixel seeds its own
+// generator from its position, so renders are repeatable.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PathTraced {
+    pub samples: usize,
+    pub termination: PathTermination,
+    pub next_event: bool,
+}
+
+impl PixelIntegrator for PathTraced {
+    fn integrate<S: Shape>(
+        &self,
+        camera: &Camera,
+        world: &World<S>,
+        emitters: &[Emitter<S>],
+        px: usize,
+        py: usize,
+    ) -> Color {
+        let n = self.samples.max(1);
+        let mut rng = XorShift::new((py * camera.hsize + px) as u64);
+        let next_event = self.next_event.then_some(emitters);
+        let mut sum = Color::new(0.0, 0.0, 0.0);
+        for _ in 0..n {
+            let (dx, dy) = (rng.next_f64(), rng.next_f64());
+            let ray = camera.ray_for_subpixel(px, py, dx, dy);
+            sum = sum + world.path_trace_with(ray, &mut rng, self.termination, next_event);
+        }
+        sum * (1.0 / n as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::camera::Camera;
+    use crate::color::Color;
+    use crate::integrator::{BoxFilterAA, PixelIntegrator, SingleSample};
+    use crate::matrix::Matrix4;
+    use crate::tuple::Tuple;
+    use crate::world::default_world;
+    use std::f64::consts::PI;
+
+    fn camera() -> Camera {
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        c
+    }
+
+    #[test]
+    fn single_sample_reproduces_render() {
+        let w = default_world();
+        let c = camera();
+        let expected = c.render(w.clone());
+        let image = c.render_integrated(w, SingleSample);
+
+        for y in 0..c.vsize {
+            for x in 0..c.hsize {
+                assert_eq!(image.pixel_at(x, y), expected.pixel_at(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn box_filter_averages_a_grid_of_sub_pixel_samples() {
+        let w = default_world();
+        let c = camera();
+        let color = BoxFilterAA(2).integrate(&c, &w, &[], 3, 2);
+        let expected = [0.25, 0.75]
+            .iter()
+            .flat_map(|dy| [0.25, 0.75].iter().map(move |dx| (*dx, *dy)))
+            .map(|(dx, dy)| w.color_at(c.ray_for_subpixel(3, 2, dx, dy)))
+            .fold(Color::new(0.0, 0.0, 0.0), |sum, color| sum + color)
+            * 0.25;
+
+        assert_eq!(color, expected);
+    }
+}
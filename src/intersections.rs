@@ -1,3 +1,6 @@
+use crate::bounds::Bounds;
+use crate::color::Color;
+use crate::matrix::Matrix4;
 use crate::ray::Ray;
 use crate::shape::Shape;
 use crate::tuple::Tuple;
@@ -8,45 +11,166 @@ use std::ptr;
 pub struct Computations<'a, S: Shape> {
     pub t: f64,
     pub object: &'a S,
+    pub parent_transform: Matrix4,
     pub point: Tuple,
     pub eyev: Tuple,
     pub normalv: Tuple,
     pub inside: bool,
     pub over_point: Tuple,
+    pub under_point: Tuple,
+    pub reflectv: Tuple,
+    // The refractive indices on the side the ray comes from and the side it goes into.
+    pub n1: f64,
+    pub n2: f64,
 }
 
-#[derive(Debug, Copy, Clone)]
+impl<'a, S: Shape> Computations<'a, S> {
+    // The points just above and just below the surface, for rays leaving on either side.
+    pub fn offset_points(&self) -> (Tuple, Tuple) {
+        offset_points(self.point, self.normalv)
+    }
+
+    // The material color at the point. Only a pattern needs the point taken out of the object's
+    // groups, so the parent transform is only inverted for one.
+    pub fn surface_color(&self) -> Color {
+        let material = self.object.material();
+        if material.pattern.is_none() {
+            return material.color;
+        }
+        material.color_at(self.object, self.parent_transform.inverse() * self.point)
+    }
+
+    // Schlick's approximation of the Fresnel reflectance, the fraction of the light that is
+    // reflected rather than refracted.
+    pub fn schlick(&self) -> f64 {
+        let mut cos = self.eyev * self.normalv;
+        if self.n1 > self.n2 {
+            let n = self.n1 / self.n2;
+            let sin2_t = n.powi(2) * (1.0 - cos.powi(2));
+            if sin2_t > 1.0 {
+                return 1.0;
+            }
+            cos = f64::sqrt(1.0 - sin2_t);
+        }
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+    }
+}
+
+// Rounding errors grow with the coordinates, so the offset does too.
+fn offset_points(point: Tuple, normalv: Tuple) -> (Tuple, Tuple) {
+    let offset = EPSILON * (1.0 + (point - Tuple::new_point(0.0, 0.0, 0.0)).magnitude());
+    (point + normalv * offset, point - normalv * offset)
+}
+
+#[derive(Debug)]
 pub struct Intersection<'a, S: Shape> {
     pub t: f64,
     pub object: &'a S,
+    // The combined transforms of the groups the object is in, from the space it sits in to world
+    // space. Built up while intersecting, so it is always that of the current group transforms.
+    pub parent_transform: Matrix4,
+}
+
+// Written out because deriving would require the shape itself to be Copy.
+impl<'a, S: Shape> Clone for Intersection<'a, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
 }
 
+impl<'a, S: Shape> Copy for Intersection<'a, S> {}
+
 impl<'a, S: Shape> Intersection<'a, S> {
     pub fn new(t: f64, object: &'a S) -> Self {
-        Self { t, object }
+        Self {
+            t,
+            object,
+            parent_transform: Matrix4::identity(),
+        }
     }
 
-    pub fn prepare_computations(&self, r: Ray) -> Computations<S> {
+    // Puts the object inside one more group, outside the ones already in parent_transform.
+    pub fn within(mut self, transform: Matrix4) -> Self {
+        self.parent_transform = transform * self.parent_transform;
+        self
+    }
+
+    pub fn normal_at(&self, point: Tuple) -> Tuple {
+        self.object.normal_at_within(self.parent_transform, point)
+    }
+
+    // Whether the ray leaves the object here, judged by the outward normal. Only meaningful for
+    // closed shapes.
+    pub fn is_exit(&self, r: Ray) -> bool {
+        self.normal_at(r.position(self.t)) * r.direction > 0.0
+    }
+
+    // Treats this as the only intersection along the ray, so the refractive indices are only
+    // right for rays entering the object from empty space.
+    pub fn prepare_computations(&self, r: Ray) -> Computations<'a, S> {
+        self.prepare_computations_with(r, &[*self])
+    }
+
+    // xs are all the intersections along the ray in order of t, this one among them. They tell
+    // which objects the ray is inside of on either side of this one.
+    pub fn prepare_computations_with(
+        &self,
+        r: Ray,
+        xs: &[Intersection<'a, S>],
+    ) -> Computations<'a, S> {
         let object = self.object;
         let point = r.position(self.t);
         let eyev = -r.direction;
-        let mut normalv = object.normal_at(point);
+        let mut normalv = self.normal_at(point);
         let inside = if normalv * eyev < 0.0 {
             normalv = -normalv;
             true
         } else {
             false
         };
-        let over_point = point + normalv * EPSILON;
+        let (over_point, under_point) = offset_points(point, normalv);
+        let reflectv = r.direction.reflect(normalv);
+        let (n1, n2) = self.refractive_indices(xs);
         Computations {
             t: self.t,
             object,
+            parent_transform: self.parent_transform,
             point,
             eyev,
             normalv,
             inside,
             over_point,
+            under_point,
+            reflectv,
+            n1,
+            n2,
+        }
+    }
+
+    fn refractive_indices(&self, xs: &[Intersection<'a, S>]) -> (f64, f64) {
+        let outermost = |containers: &[&S]| {
+            containers
+                .last()
+                .map_or(1.0, |object| object.material().refractive_index)
+        };
+        let mut containers: Vec<&S> = Vec::new();
+        let mut n1 = 1.0;
+        for i in xs {
+            if i == self {
+                n1 = outermost(&containers);
+            }
+            match containers.iter().position(|&c| ptr::eq(c, i.object)) {
+                Some(index) => {
+                    containers.remove(index);
+                }
+                None => containers.push(i.object),
+            }
+            if i == self {
+                return (n1, outermost(&containers));
+            }
         }
+        (n1, 1.0)
     }
 }
 
@@ -60,20 +184,80 @@ impl<'a, S: Shape> PartialEq for Intersection<'a, S> {
 pub struct Intersections<'a, S: Shape> {
     inner: Vec<Intersection<'a, S>>,
     hit: Option<usize>,
+    sorted: bool,
 }
 
 impl<'a, S: Shape> Intersections<'a, S> {
-    pub fn new(mut intersections: Vec<Intersection<'a, S>>) -> Self {
-        intersections.sort_by(|lhs, rhs| lhs.t.partial_cmp(&rhs.t).unwrap());
-        let hit = intersections
+    pub fn new(intersections: Vec<Intersection<'a, S>>) -> Self {
+        let mut result = Self::new_unsorted(intersections);
+        result.sort();
+        result
+    }
+
+    pub fn new_unsorted(intersections: Vec<Intersection<'a, S>>) -> Self {
+        Self {
+            inner: intersections,
+            hit: None,
+            sorted: false,
+        }
+    }
+
+    pub fn sort(&mut self) {
+        self.inner
+            .sort_by(|lhs, rhs| lhs.t.partial_cmp(&rhs.t).unwrap());
+        self.find_hit();
+    }
+
+    // Sorts, then tidies up runs of intersections less than EPSILON apart, which rounding could
+    // otherwise put in any order. Two of them on the same object are a ray grazing it, so both go.
+    // Of the rest, the ray enters objects before it leaves others, so surfaces that touch overlap
+    // rather than leave a gap, and CSG sees one solid where two shapes meet.
+    pub fn merge_coincident(&mut self, r: Ray) {
+        self.sort();
+        let mut merged = Vec::with_capacity(self.inner.len());
+        let mut start = 0;
+        while start < self.inner.len() {
+            let mut end = start + 1;
+            while end < self.inner.len() && self.inner[end].t - self.inner[end - 1].t < EPSILON {
+                end += 1;
+            }
+            let mut run: Vec<Intersection<'a, S>> = Vec::with_capacity(end - start);
+            for x in &self.inner[start..end] {
+                match run.iter().position(|y| ptr::eq(y.object, x.object)) {
+                    Some(index) => {
+                        run.remove(index);
+                    }
+                    None => run.push(*x),
+                }
+            }
+            run.sort_by_key(|x| x.is_exit(r));
+            merged.extend(run);
+            start = end;
+        }
+        self.inner = merged;
+        self.find_hit();
+    }
+
+    fn find_hit(&mut self) {
+        self.hit = self
+            .inner
             .iter()
             .enumerate()
             .find(|(_, e)| e.t.is_sign_positive())
             .map(|(i, _)| i);
-        Self {
-            inner: intersections,
-            hit,
+        self.sorted = true;
+    }
+
+    // See Intersection::within.
+    pub fn within(mut self, transform: Matrix4) -> Self {
+        for x in self.inner.iter_mut() {
+            *x = x.within(transform);
         }
+        self
+    }
+
+    pub fn into_vec(self) -> Vec<Intersection<'a, S>> {
+        self.inner
     }
 
     pub fn len(&self) -> usize {
@@ -85,6 +269,13 @@ impl<'a, S: Shape> Intersections<'a, S> {
     }
 
     pub fn hit(&self) -> Option<&Intersection<'a, S>> {
+        if !self.sorted {
+            return self
+                .inner
+                .iter()
+                .filter(|e| e.t.is_sign_positive())
+                .min_by(|lhs, rhs| lhs.t.partial_cmp(&rhs.t).unwrap());
+        }
         match self.hit {
             Some(hit) => Some(&self[hit]),
             None => None,
@@ -92,6 +283,114 @@ impl<'a, S: Shape> Intersections<'a, S> {
     }
 }
 
+pub fn intersect_all<S: Shape>(shapes: &[S], r: Ray) -> Intersections<'_, S> {
+    let capacity = shapes.iter().map(|shape| shape.max_intersections()).sum();
+    let mut xs = Vec::with_capacity(capacity);
+    for shape in shapes.iter() {
+        xs.extend(shape.intersect(r).into_vec());
+    }
+    Intersections::new(xs)
+}
+
+// Shapes along with their boxes in the space they sit in and the box around them all. The boxes
+// are worked out as shapes are added or changed, never per ray, so a ray costs one box test for the
+// lot when it misses them. The total of their max_intersections and whether any of them casts a
+// shadow are kept the same way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundedShapes<S: Shape> {
+    shapes: Vec<S>,
+    bounds: Vec<Bounds>,
+    all: Bounds,
+    max_intersections: usize,
+    casts_shadow: bool,
+}
+
+impl<S: Shape> BoundedShapes<S> {
+    pub fn new() -> Self {
+        Self {
+            shapes: Vec::new(),
+            bounds: Vec::new(),
+            all: Bounds::empty(),
+            max_intersections: 0,
+            casts_shadow: false,
+        }
+    }
+
+    pub fn push(&mut self, shape: S) {
+        let bounds = shape.parent_space_bounds();
+        self.all.merge(&bounds);
+        self.max_intersections += shape.max_intersections();
+        self.casts_shadow |= shape.casts_shadow();
+        self.shapes.push(shape);
+        self.bounds.push(bounds);
+    }
+
+    // Changes one shape and works out its box again, along with the box around them all.
+    pub fn update(&mut self, index: usize, f: impl FnOnce(&mut S)) {
+        self.max_intersections -= self.shapes[index].max_intersections();
+        f(&mut self.shapes[index]);
+        self.max_intersections += self.shapes[index].max_intersections();
+        self.bounds[index] = self.shapes[index].parent_space_bounds();
+        self.all = Bounds::empty();
+        for b in &self.bounds {
+            self.all.merge(b);
+        }
+        self.casts_shadow = self.shapes.iter().any(|s| s.casts_shadow());
+    }
+
+    // Changes every shape, then works out all the boxes again in one go.
+    pub fn update_all(&mut self, mut f: impl FnMut(&mut S)) {
+        self.shapes.iter_mut().for_each(&mut f);
+        self.bounds = self
+            .shapes
+            .iter()
+            .map(|s| s.parent_space_bounds())
+            .collect();
+        self.all = Bounds::empty();
+        for b in &self.bounds {
+            self.all.merge(b);
+        }
+        self.max_intersections = self.shapes.iter().map(|s| s.max_intersections()).sum();
+        self.casts_shadow = self.shapes.iter().any(|s| s.casts_shadow());
+    }
+
+    pub fn shapes(&self) -> &[S] {
+        &self.shapes
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        self.all
+    }
+
+    pub fn max_intersections(&self) -> usize {
+        self.max_intersections
+    }
+
+    pub fn casts_shadow(&self) -> bool {
+        self.casts_shadow
+    }
+
+    // Like intersect_all, but skips every shape whose box the ray misses.
+    pub fn intersect(&self, r: Ray) -> Intersections<'_, S> {
+        if !self.all.intersects(r) {
+            return Intersections::new(Vec::new());
+        }
+        let mut xs = Vec::with_capacity(self.max_intersections);
+        for (shape, b) in self.shapes.iter().zip(&self.bounds) {
+            if b.intersects(r) {
+                xs.extend(shape.intersect(r).into_vec());
+            }
+        }
+        Intersections::new(xs)
+    }
+}
+
+impl<S: Shape> Default for BoundedShapes<S> {
+    fn default() -> Self {
+        BoundedShapes::new()
+    }
+}
+
 impl<'a, S: Shape> Index<usize> for Intersections<'a, S> {
     type Output = Intersection<'a, S>;
 
@@ -111,10 +410,16 @@ impl<'a, S: Shape> Deref for Intersections<'a, S> {
 #[cfg(test)]
 mod tests {
     use crate::assert_float_eq;
-    use crate::intersections::{Intersection, Intersections};
+    use crate::intersections::{intersect_all, BoundedShapes, Intersection, Intersections};
+    use crate::matrix::Matrix4;
+    use crate::plane::Plane;
     use crate::ray::Ray;
+    use crate::shape::Shape;
     use crate::sphere::Sphere;
+    use crate::testing::{glass_sphere, RecordingShape};
     use crate::tuple::Tuple;
+    use crate::world::default_world;
+    use crate::EPSILON;
     use std::ptr;
 
     #[test]
@@ -189,6 +494,93 @@ mod tests {
         assert_eq!(i, Some(&i4));
     }
 
+    #[test]
+    fn sorting_unsorted_intersections_matches_new() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(5.0, &s);
+        let i2 = Intersection::new(7.0, &s);
+        let i3 = Intersection::new(-3.0, &s);
+        let i4 = Intersection::new(2.0, &s);
+        let expected = Intersections::new(vec![i1, i2, i3, i4]);
+        let mut xs = Intersections::new_unsorted(vec![i1, i2, i3, i4]);
+
+        assert_eq!(xs[0], i1);
+
+        xs.sort();
+
+        assert_eq!(*xs, *expected);
+        assert_eq!(xs.hit(), Some(&i4));
+    }
+
+    #[test]
+    fn the_hit_of_unsorted_intersections() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(5.0, &s);
+        let i2 = Intersection::new(-3.0, &s);
+        let i3 = Intersection::new(2.0, &s);
+        let xs = Intersections::new_unsorted(vec![i1, i2, i3]);
+
+        assert_eq!(xs.hit(), Some(&i3));
+    }
+
+    #[test]
+    fn merging_coincident_intersections() {
+        let mut left = Sphere::new();
+        left.transform = Matrix4::translation(-1.0, 0.0, 0.0);
+        let mut right = Sphere::new();
+        right.transform = Matrix4::translation(1.0, 0.0, 0.0);
+        let r = Ray::new(
+            Tuple::new_point(-5.0, 0.0, 0.0),
+            Tuple::new_vector(1.0, 0.0, 0.0),
+        );
+        let mut xs = Intersections::new(vec![
+            Intersection::new(3.0, &left),
+            Intersection::new(5.0 - EPSILON / 4.0, &left),
+            Intersection::new(5.0 + EPSILON / 4.0, &right),
+            Intersection::new(7.0, &right),
+        ]);
+        xs.merge_coincident(r);
+
+        assert_eq!(xs.len(), 4);
+        assert!(ptr::eq(xs[1].object, &right));
+        assert!(ptr::eq(xs[2].object, &left));
+        assert_eq!(xs.hit().unwrap().t, 3.0);
+
+        let graze = Ray::new(
+            Tuple::new_point(-5.0, 1.0, 0.0),
+            Tuple::new_vector(1.0, 0.0, 0.0),
+        );
+        let mut xs = Intersections::new(vec![
+            Intersection::new(4.0, &left),
+            Intersection::new(4.0 + EPSILON / 4.0, &left),
+        ]);
+        xs.merge_coincident(graze);
+
+        assert!(xs.is_empty());
+        assert_eq!(xs.hit(), None);
+    }
+
+    #[test]
+    fn intersecting_a_slice_of_shapes() {
+        let mut s2 = Sphere::new();
+        s2.transform = Matrix4::scaling(0.5, 0.5, 0.5);
+        let shapes = [Sphere::new(), s2];
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = intersect_all(&shapes, r);
+        let w = default_world();
+        let expected = w.intersect_world(r);
+
+        assert_eq!(xs.len(), 4);
+        for (x, e) in xs.iter().zip(expected.iter()) {
+            assert_float_eq!(x.t, e.t);
+        }
+        assert_float_eq!(xs[0].t, 4.0);
+        assert_float_eq!(xs[3].t, 6.0);
+    }
+
     #[test]
     fn precomputing_the_state_of_an_intersection() {
         let r = Ray::new(
@@ -206,6 +598,106 @@ mod tests {
         assert_eq!(comps.normalv, Tuple::new_vector(0.0, 0.0, -1.0));
     }
 
+    #[test]
+    fn precomputing_the_reflection_vector() {
+        let shape = Plane::new();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 1.0, -1.0),
+            Tuple::new_vector(0.0, -f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0),
+        );
+        let i = Intersection::new(f64::sqrt(2.0), &shape);
+        let comps = i.prepare_computations(r);
+
+        assert_eq!(
+            comps.reflectv,
+            Tuple::new_vector(0.0, f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0)
+        );
+    }
+
+    #[test]
+    fn finding_n1_and_n2_at_various_intersections() {
+        let mut a = glass_sphere();
+        a.transform = Matrix4::scaling(2.0, 2.0, 2.0);
+        a.material.refractive_index = 1.5;
+        let mut b = glass_sphere();
+        b.transform = Matrix4::translation(0.0, 0.0, -0.25);
+        b.material.refractive_index = 2.0;
+        let mut c = glass_sphere();
+        c.transform = Matrix4::translation(0.0, 0.0, 0.25);
+        c.material.refractive_index = 2.5;
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -4.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = Intersections::new(vec![
+            Intersection::new(2.0, &a),
+            Intersection::new(2.75, &b),
+            Intersection::new(3.25, &c),
+            Intersection::new(4.75, &b),
+            Intersection::new(5.25, &c),
+            Intersection::new(6.0, &a),
+        ]);
+        let expected = [
+            (1.0, 1.5),
+            (1.5, 2.0),
+            (2.0, 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.0),
+        ];
+
+        for (i, (n1, n2)) in expected.into_iter().enumerate() {
+            let comps = xs[i].prepare_computations_with(r, &xs);
+            assert_float_eq!(comps.n1, n1);
+            assert_float_eq!(comps.n2, n2);
+        }
+    }
+
+    #[test]
+    fn the_schlick_approximation_under_total_internal_reflection() {
+        let shape = glass_sphere();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, f64::sqrt(2.0) / 2.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let xs = Intersections::new(vec![
+            Intersection::new(-f64::sqrt(2.0) / 2.0, &shape),
+            Intersection::new(f64::sqrt(2.0) / 2.0, &shape),
+        ]);
+        let comps = xs[1].prepare_computations_with(r, &xs);
+
+        assert_float_eq!(comps.schlick(), 1.0);
+    }
+
+    #[test]
+    fn the_schlick_approximation_with_a_perpendicular_viewing_angle() {
+        let shape = glass_sphere();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let xs = Intersections::new(vec![
+            Intersection::new(-1.0, &shape),
+            Intersection::new(1.0, &shape),
+        ]);
+        let comps = xs[1].prepare_computations_with(r, &xs);
+
+        assert_float_eq!(comps.schlick(), 0.04);
+    }
+
+    #[test]
+    fn the_schlick_approximation_with_small_angle_and_n2_greater_than_n1() {
+        let shape = glass_sphere();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.99, -2.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = Intersections::new(vec![Intersection::new(1.8589, &shape)]);
+        let comps = xs[0].prepare_computations_with(r, &xs);
+
+        assert!((comps.schlick() - 0.48873).abs() < 0.0001);
+    }
+
     #[test]
     fn the_hit_when_intersection_occurs_on_the_outside() {
         let r = Ray::new(
@@ -234,4 +726,154 @@ mod tests {
         assert!(comps.inside);
         assert_eq!(comps.normalv, Tuple::new_vector(0.0, 0.0, -1.0));
     }
+
+    #[test]
+    fn a_ray_enters_a_sphere_at_the_first_intersection_and_exits_at_the_second() {
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let s = Sphere::new();
+        let xs = s.intersect(r);
+
+        assert!(!xs[0].is_exit(r));
+        assert!(xs[1].is_exit(r));
+    }
+
+    #[test]
+    fn a_ray_from_inside_a_sphere_hits_an_exit() {
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let s = Sphere::new();
+        let xs = s.intersect(r);
+
+        assert!(xs.hit().unwrap().is_exit(r));
+    }
+
+    #[test]
+    fn the_offset_grows_with_the_distance_from_the_origin() {
+        let r = Ray::new(
+            Tuple::new_point(0.0, 1000.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let mut s = Sphere::new();
+        s.transform = Matrix4::translation(0.0, 1000.0, 1.0);
+        let i = Intersection::new(5.0, &s);
+        let comps = i.prepare_computations(r);
+        let offset = comps.point.z - comps.over_point.z;
+
+        assert_float_eq!(comps.point.z, 0.0);
+        assert!((offset - EPSILON * 1001.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_surface_far_from_the_origin_does_not_shadow_itself() {
+        let mut w = default_world();
+        for object in w.objects.iter_mut() {
+            object.transform = Matrix4::translation(1000.0, 1000.0, 1000.0)
+                * Matrix4::scaling(100.0, 100.0, 100.0)
+                * object.transform;
+        }
+        w.light.as_mut().unwrap().position = Tuple::new_point(1000.0, 1000.0, 0.0);
+        for i in 0..50 {
+            let angle = i as f64 * 0.002;
+            let r = Ray::new(
+                Tuple::new_point(1000.0, 1000.0, 0.0),
+                Tuple::new_vector(angle.sin(), 0.0, angle.cos()),
+            );
+            let xs = w.intersect_world(r);
+            let comps = xs.hit().unwrap().prepare_computations(r);
+
+            assert!(!w.is_shadowed(comps.over_point));
+        }
+    }
+
+    #[test]
+    fn the_over_and_under_points_straddle_the_point() {
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let mut s = Sphere::new();
+        s.transform = Matrix4::translation(0.0, 0.0, 1.0);
+        let i = Intersection::new(5.0, &s);
+        let comps = i.prepare_computations(r);
+
+        assert!(comps.under_point.z > EPSILON / 2.0);
+        assert!(comps.point.z < comps.under_point.z);
+        assert_eq!(
+            comps.over_point - comps.point,
+            comps.point - comps.under_point
+        );
+        assert_eq!(comps.offset_points(), (comps.over_point, comps.under_point));
+    }
+
+    #[test]
+    fn shapes_are_only_intersected_when_the_ray_hits_their_bounds() {
+        let mut near = RecordingShape::new();
+        near.transform = Matrix4::identity().translate(-2.0, 0.0, 0.0);
+        let mut far = RecordingShape::new();
+        far.transform = Matrix4::identity().translate(2.0, 0.0, 0.0);
+        let mut shapes = BoundedShapes::new();
+        shapes.push(near);
+        shapes.push(far);
+
+        let miss = Ray::new(
+            Tuple::new_point(0.0, 5.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        shapes.intersect(miss);
+        assert!(shapes.shapes()[0].rays().is_empty());
+        assert!(shapes.shapes()[1].rays().is_empty());
+
+        let hit = Ray::new(
+            Tuple::new_point(-2.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        shapes.intersect(hit);
+        assert_eq!(shapes.shapes()[0].rays().len(), 1);
+        assert!(shapes.shapes()[1].rays().is_empty());
+    }
+
+    #[test]
+    fn bounded_shapes_keep_the_box_around_them_all() {
+        let mut near = RecordingShape::new();
+        near.transform = Matrix4::identity().translate(-2.0, 0.0, 0.0);
+        let mut far = RecordingShape::new();
+        far.transform = Matrix4::identity().translate(2.0, 0.0, 0.0);
+        let mut shapes = BoundedShapes::new();
+
+        assert!(shapes.bounds().is_empty());
+
+        shapes.push(near);
+        shapes.push(far);
+
+        assert_eq!(shapes.bounds().minimum, Tuple::new_point(-3.0, -1.0, -1.0));
+        assert_eq!(shapes.bounds().maximum, Tuple::new_point(3.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn bounds_are_only_worked_out_again_when_a_shape_changes() {
+        let mut shapes = BoundedShapes::new();
+        shapes.push(RecordingShape::new());
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        shapes.intersect(r);
+        shapes.intersect(r);
+        shapes.bounds();
+
+        assert_eq!(shapes.shapes()[0].bounds_queries(), 1);
+
+        shapes.update(0, |s| s.transform = Matrix4::translation(5.0, 0.0, 0.0));
+
+        assert_eq!(shapes.shapes()[0].bounds_queries(), 2);
+        assert_eq!(shapes.bounds().minimum, Tuple::new_point(4.0, -1.0, -1.0));
+        assert_eq!(shapes.bounds().maximum, Tuple::new_point(6.0, 1.0, 1.0));
+        shapes.intersect(r);
+        assert_eq!(shapes.shapes()[0].rays().len(), 2);
+    }
 }
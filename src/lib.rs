@@ -1,14 +1,30 @@
+pub mod bounds;
 pub mod camera;
 pub mod canvas;
 pub mod color;
+pub mod cone;
+pub mod csg;
+pub mod cube;
+pub mod group;
+pub mod integrator;
 pub mod intersections;
 pub mod light;
 pub mod material;
+pub mod math;
 pub mod matrix;
+pub mod obj;
+pub mod pattern;
 pub mod plane;
+pub mod random;
 pub mod ray;
+pub mod scenes;
 pub mod shape;
 pub mod sphere;
+pub mod stats;
+pub mod svg;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod triangle;
 pub mod tuple;
 pub mod world;
 
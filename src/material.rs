@@ -1,38 +1,90 @@
 use crate::color::Color;
 use crate::float_eq;
 use crate::light::PointLight;
+use crate::pattern::{pattern_at_shape, Pattern};
+use crate::shape::Shape;
 use crate::tuple::Tuple;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub struct Material {
     pub color: Color,
+    // Used instead of color when set.
+    pub pattern: Option<Box<dyn Pattern>>,
     pub ambient: f64,
     pub diffuse: f64,
     pub specular: f64,
     pub shininess: f64,
+    // Tints the highlight, as metals do. Without it the highlight has the light's color.
+    pub specular_color: Option<Color>,
+    pub single_sided: bool,
+    pub cast_shadow: bool,
+    // Light given off by the surface itself. Only the path tracer uses it.
+    pub emissive: Color,
+    pub reflective: f64,
+    // Spreads reflections over a cone around the mirror direction, given as the tangent of its
+    // half angle. Zero is a perfect mirror.
+    pub reflection_roughness: f64,
+    pub transparency: f64,
+    pub refractive_index: f64,
 }
 
 impl Material {
     pub fn new() -> Self {
         Self {
             color: Color::new(1.0, 1.0, 1.0),
+            pattern: None,
             ambient: 0.1,
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            specular_color: None,
+            single_sided: false,
+            cast_shadow: true,
+            emissive: Color::new(0.0, 0.0, 0.0),
+            reflective: 0.0,
+            reflection_roughness: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
         }
     }
 
-    pub fn lighting(
+    pub fn lighting<S: Shape>(
         &self,
+        object: &S,
         light: PointLight,
         point: Tuple,
         eyev: Tuple,
         normalv: Tuple,
         in_shadow: bool,
     ) -> Color {
-        let effective_color = self.color * light.intensity;
-        let ambient = effective_color * self.ambient;
+        let color = self.color_at(object, point);
+        self.lighting_with_ambient_occlusion(color, light, point, eyev, normalv, in_shadow, 1.0)
+    }
+
+    // The surface color at a point in the space the object sits in, which is world space unless
+    // the object is in a group.
+    pub fn color_at<S: Shape>(&self, object: &S, point: Tuple) -> Color {
+        match &self.pattern {
+            Some(pattern) => pattern_at_shape(pattern.as_ref(), object, point),
+            None => self.color,
+        }
+    }
+
+    // Takes the surface color rather than the object, since the caller knows where the point is
+    // relative to the object's groups.
+    #[allow(clippy::too_many_arguments)]
+    pub fn lighting_with_ambient_occlusion(
+        &self,
+        color: Color,
+        light: PointLight,
+        point: Tuple,
+        eyev: Tuple,
+        normalv: Tuple,
+        in_shadow: bool,
+        occlusion: f64,
+    ) -> Color {
+        let effective_color = color * light.intensity;
+        let ambient = effective_color * self.ambient * occlusion;
         if in_shadow {
             return ambient;
         }
@@ -50,7 +102,10 @@ impl Material {
                 black
             } else {
                 let factor = reflect_dot_eye.powf(self.shininess);
-                light.intensity * self.specular * factor
+                let highlight = self
+                    .specular_color
+                    .map_or(light.intensity, |color| color * light.intensity);
+                highlight * self.specular * factor
             };
             (diffuse, specular)
         };
@@ -67,10 +122,19 @@ impl Default for Material {
 impl PartialEq for Material {
     fn eq(&self, other: &Self) -> bool {
         self.color == other.color
+            && self.pattern == other.pattern
             && float_eq(self.ambient, other.ambient)
             && float_eq(self.diffuse, other.diffuse)
             && float_eq(self.specular, other.specular)
             && float_eq(self.shininess, other.shininess)
+            && self.specular_color == other.specular_color
+            && self.single_sided == other.single_sided
+            && self.cast_shadow == other.cast_shadow
+            && self.emissive == other.emissive
+            && float_eq(self.reflective, other.reflective)
+            && float_eq(self.reflection_roughness, other.reflection_roughness)
+            && float_eq(self.transparency, other.transparency)
+            && float_eq(self.refractive_index, other.refractive_index)
     }
 }
 
@@ -79,6 +143,8 @@ mod tests {
     use crate::color::Color;
     use crate::light::PointLight;
     use crate::material::Material;
+    use crate::pattern::StripePattern;
+    use crate::sphere::Sphere;
     use crate::tuple::Tuple;
 
     #[test]
@@ -86,10 +152,19 @@ mod tests {
         let m = Material::new();
 
         assert_eq!(m.color, Color::new(1.0, 1.0, 1.0));
+        assert_eq!(m.pattern, None);
         assert_eq!(m.ambient, 0.1);
         assert_eq!(m.diffuse, 0.9);
         assert_eq!(m.specular, 0.9);
         assert_eq!(m.shininess, 200.0);
+        assert_eq!(m.specular_color, None);
+        assert!(!m.single_sided);
+        assert!(m.cast_shadow);
+        assert_eq!(m.emissive, Color::new(0.0, 0.0, 0.0));
+        assert_eq!(m.reflective, 0.0);
+        assert_eq!(m.reflection_roughness, 0.0);
+        assert_eq!(m.transparency, 0.0);
+        assert_eq!(m.refractive_index, 1.0);
     }
 
     #[test]
@@ -100,12 +175,44 @@ mod tests {
         let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
         let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
         let light = PointLight::new(Tuple::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
-        let result = m.lighting(light, position, eyev, normalv, false);
+        let result = m.lighting(&Sphere::new(), light, position, eyev, normalv, false);
         let expected = Color::new(1.9, 1.9, 1.9);
 
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn lighting_a_metal_with_a_tinted_highlight() {
+        let mut m = Material::new();
+        m.specular_color = Some(Color::new(1.0, 0.8, 0.4));
+        let position = Tuple::new_point(0.0, 0.0, 0.0);
+
+        let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let result = m.lighting(&Sphere::new(), light, position, eyev, normalv, false);
+        let expected = Color::new(1.9, 1.72, 1.36);
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn a_tinted_highlight_is_also_lit_by_the_light_color() {
+        let mut m = Material::new();
+        m.ambient = 0.0;
+        m.diffuse = 0.0;
+        m.specular_color = Some(Color::new(1.0, 0.8, 0.4));
+        let position = Tuple::new_point(0.0, 0.0, 0.0);
+
+        let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::new_point(0.0, 0.0, -10.0), Color::new(0.5, 1.0, 1.0));
+        let result = m.lighting(&Sphere::new(), light, position, eyev, normalv, false);
+        let expected = Color::new(0.45, 0.72, 0.36);
+
+        assert_eq!(result, expected);
+    }
+
     #[test]
     fn lighting_with_the_eye_between_the_light_and_the_surface_eye_offset_45_degrees() {
         let m = Material::new();
@@ -114,7 +221,7 @@ mod tests {
         let eyev = Tuple::new_vector(0.0, f64::sqrt(2.0) / 2.0, -f64::sqrt(2.0) / 2.0);
         let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
         let light = PointLight::new(Tuple::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
-        let result = m.lighting(light, position, eyev, normalv, false);
+        let result = m.lighting(&Sphere::new(), light, position, eyev, normalv, false);
         let expected = Color::new(1.0, 1.0, 1.0);
 
         assert_eq!(result, expected);
@@ -131,7 +238,7 @@ mod tests {
             Tuple::new_point(0.0, 10.0, -10.0),
             Color::new(1.0, 1.0, 1.0),
         );
-        let result = m.lighting(light, position, eyev, normalv, false);
+        let result = m.lighting(&Sphere::new(), light, position, eyev, normalv, false);
         let expected = Color::new(0.7364, 0.7364, 0.7364);
 
         assert_eq!(result, expected);
@@ -148,7 +255,7 @@ mod tests {
             Tuple::new_point(0.0, 10.0, -10.0),
             Color::new(1.0, 1.0, 1.0),
         );
-        let result = m.lighting(light, position, eyev, normalv, false);
+        let result = m.lighting(&Sphere::new(), light, position, eyev, normalv, false);
         let expected = Color::new(1.6364, 1.6364, 1.6364);
 
         assert_eq!(result, expected);
@@ -162,7 +269,7 @@ mod tests {
         let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
         let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
         let light = PointLight::new(Tuple::new_point(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0));
-        let result = m.lighting(light, position, eyev, normalv, false);
+        let result = m.lighting(&Sphere::new(), light, position, eyev, normalv, false);
         let expected = Color::new(0.1, 0.1, 0.1);
 
         assert_eq!(result, expected);
@@ -177,8 +284,59 @@ mod tests {
         let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
         let light = PointLight::new(Tuple::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
         let in_shadow = true;
-        let result = m.lighting(light, position, eyev, normalv, in_shadow);
+        let result = m.lighting(&Sphere::new(), light, position, eyev, normalv, in_shadow);
 
         assert_eq!(result, Color::new(0.1, 0.1, 0.1));
     }
+
+    #[test]
+    fn lighting_with_an_occluded_ambient_term() {
+        let m = Material::new();
+        let position = Tuple::new_point(0.0, 0.0, 0.0);
+
+        let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let result =
+            m.lighting_with_ambient_occlusion(m.color, light, position, eyev, normalv, true, 0.5);
+
+        assert_eq!(result, Color::new(0.05, 0.05, 0.05));
+    }
+
+    #[test]
+    fn lighting_with_a_pattern_applied() {
+        let m = Material {
+            pattern: Some(Box::new(StripePattern::new(
+                Color::new(1.0, 1.0, 1.0),
+                Color::new(0.0, 0.0, 0.0),
+            ))),
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            ..Material::new()
+        };
+        let object = Sphere::new();
+        let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let c1 = m.lighting(
+            &object,
+            light,
+            Tuple::new_point(0.9, 0.0, 0.0),
+            eyev,
+            normalv,
+            false,
+        );
+        let c2 = m.lighting(
+            &object,
+            light,
+            Tuple::new_point(1.1, 0.0, 0.0),
+            eyev,
+            normalv,
+            false,
+        );
+
+        assert_eq!(c1, Color::new(1.0, 1.0, 1.0));
+        assert_eq!(c2, Color::new(0.0, 0.0, 0.0));
+    }
 }
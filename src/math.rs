@@ -0,0 +1,44 @@
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Option<(f64, f64)> {
+    let discriminant = b.powi(2) - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let t0 = (-b - discriminant.sqrt()) / (2.0 * a);
+    let t1 = (-b + discriminant.sqrt()) / (2.0 * a);
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_float_eq;
+    use crate::math::solve_quadratic;
+
+    #[test]
+    fn solving_a_quadratic_with_two_real_roots() {
+        let (t0, t1) = solve_quadratic(1.0, -5.0, 6.0).unwrap();
+
+        assert_float_eq!(t0, 2.0);
+        assert_float_eq!(t1, 3.0);
+    }
+
+    #[test]
+    fn the_roots_are_returned_in_ascending_order() {
+        let (t0, t1) = solve_quadratic(-1.0, 5.0, -6.0).unwrap();
+
+        assert_float_eq!(t0, 2.0);
+        assert_float_eq!(t1, 3.0);
+    }
+
+    #[test]
+    fn solving_a_quadratic_with_a_double_root() {
+        let (t0, t1) = solve_quadratic(1.0, -4.0, 4.0).unwrap();
+
+        assert_float_eq!(t0, 2.0);
+        assert_float_eq!(t1, 2.0);
+    }
+
+    #[test]
+    fn solving_a_quadratic_without_real_roots() {
+        assert_eq!(solve_quadratic(1.0, 0.0, 1.0), None);
+    }
+}
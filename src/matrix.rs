@@ -156,7 +156,31 @@ impl Matrix2 {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TransformOp {
+    Translate(f64, f64, f64),
+    Scale(f64, f64, f64),
+    RotateX(f64),
+    RotateY(f64),
+    RotateZ(f64),
+    Shear(f64, f64, f64, f64, f64, f64),
+}
+
 impl Matrix4 {
+    pub fn from_ops(ops: &[TransformOp]) -> Self {
+        ops.iter()
+            .fold(Self::identity(), |transform, op| match *op {
+                TransformOp::Translate(x, y, z) => transform.translate(x, y, z),
+                TransformOp::Scale(x, y, z) => transform.scale(x, y, z),
+                TransformOp::RotateX(r) => transform.rotate_x(r),
+                TransformOp::RotateY(r) => transform.rotate_y(r),
+                TransformOp::RotateZ(r) => transform.rotate_z(r),
+                TransformOp::Shear(xy, xz, yx, yz, zx, zy) => {
+                    transform.shear(xy, xz, yx, yz, zx, zy)
+                }
+            })
+    }
+
     pub fn identity() -> Self {
         Matrix4::new([
             [1.0, 0.0, 0.0, 0.0],
@@ -258,6 +282,34 @@ impl Matrix4 {
         ]);
         orientation * Matrix4::translation(-from.x, -from.y, -from.z)
     }
+
+    // Negates z, converting between left- and right-handed coordinates. It is its own inverse.
+    pub fn flip_handedness() -> Self {
+        Self::scaling(1.0, 1.0, -1.0)
+    }
+
+    pub fn row(&self, i: usize) -> Tuple {
+        let [x, y, z, w] = self[i];
+        Tuple::new(x, y, z, w)
+    }
+
+    pub fn column(&self, i: usize) -> Tuple {
+        Tuple::new(self[0][i], self[1][i], self[2][i], self[3][i])
+    }
+
+    pub fn transform_point(&self, p: Tuple) -> Tuple {
+        debug_assert!(p.is_point(), "expected a point, got {:?}", p);
+        *self * p
+    }
+
+    pub fn transform_vector(&self, v: Tuple) -> Tuple {
+        debug_assert!(v.is_vector(), "expected a vector, got {:?}", v);
+        Tuple::new_vector(
+            self[0][0] * v.x + self[0][1] * v.y + self[0][2] * v.z,
+            self[1][0] * v.x + self[1][1] * v.y + self[1][2] * v.z,
+            self[2][0] * v.x + self[2][1] * v.y + self[2][2] * v.z,
+        )
+    }
 }
 
 impl Mul<Tuple> for Matrix4 {
@@ -276,7 +328,7 @@ impl Mul<Tuple> for Matrix4 {
 #[cfg(test)]
 mod tests {
     use crate::assert_float_eq;
-    use crate::matrix::{Matrix2, Matrix3, Matrix4};
+    use crate::matrix::{Matrix2, Matrix3, Matrix4, TransformOp};
     use crate::tuple::Tuple;
     use std::f64::consts::PI;
 
@@ -640,6 +692,37 @@ mod tests {
         assert_eq!(transform * v, v);
     }
 
+    #[test]
+    fn transforming_a_point_applies_the_translation() {
+        let transform = Matrix4::translation(5.0, -3.0, 2.0);
+        let p = Tuple::new_point(-3.0, 4.0, 5.0);
+
+        assert_eq!(
+            transform.transform_point(p),
+            Tuple::new_point(2.0, 1.0, 7.0)
+        );
+    }
+
+    #[test]
+    fn transforming_a_vector_ignores_the_translation() {
+        let transform = Matrix4::translation(5.0, -3.0, 2.0).scale(2.0, 2.0, 2.0);
+        let v = Tuple::new_vector(-3.0, 4.0, 5.0);
+
+        assert_eq!(
+            transform.transform_vector(v),
+            Tuple::new_vector(-6.0, 8.0, 10.0)
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "expected a vector")]
+    fn transforming_a_point_as_a_vector_is_rejected() {
+        let transform = Matrix4::translation(5.0, -3.0, 2.0);
+
+        transform.transform_vector(Tuple::new_point(1.0, 2.0, 3.0));
+    }
+
     #[test]
     fn a_scaling_matrix_applied_to_a_point() {
         let transform = Matrix4::scaling(2.0, 3.0, 4.0);
@@ -824,6 +907,37 @@ mod tests {
         assert_eq!(transform * p, expected);
     }
 
+    #[test]
+    fn composing_a_transformation_from_a_list_of_operations() {
+        let ops = [
+            TransformOp::Scale(5.0, 5.0, 5.0),
+            TransformOp::Translate(10.0, 5.0, 7.0),
+        ];
+        let expected = Matrix4::identity()
+            .scale(5.0, 5.0, 5.0)
+            .translate(10.0, 5.0, 7.0);
+
+        assert_eq!(Matrix4::from_ops(&ops), expected);
+    }
+
+    #[test]
+    fn composing_every_kind_of_operation_applies_them_in_order() {
+        let ops = [
+            TransformOp::RotateX(PI / 2.0),
+            TransformOp::RotateY(PI / 4.0),
+            TransformOp::RotateZ(PI / 3.0),
+            TransformOp::Shear(1.0, 0.0, 0.0, 0.0, 0.0, 0.0),
+        ];
+        let expected = Matrix4::identity()
+            .rotate_x(PI / 2.0)
+            .rotate_y(PI / 4.0)
+            .rotate_z(PI / 3.0)
+            .shear(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+
+        assert_eq!(Matrix4::from_ops(&ops), expected);
+        assert_eq!(Matrix4::from_ops(&[]), Matrix4::identity());
+    }
+
     #[test]
     fn the_transformation_matrix_for_the_default_orientation() {
         let from = Tuple::new_point(0.0, 0.0, 0.0);
@@ -869,4 +983,54 @@ mod tests {
         ]);
         assert_eq!(t, expected);
     }
+
+    #[test]
+    fn the_rows_and_columns_of_the_identity_matrix_are_the_basis() {
+        let m = Matrix4::identity();
+        let basis = [
+            Tuple::new(1.0, 0.0, 0.0, 0.0),
+            Tuple::new(0.0, 1.0, 0.0, 0.0),
+            Tuple::new(0.0, 0.0, 1.0, 0.0),
+            Tuple::new(0.0, 0.0, 0.0, 1.0),
+        ];
+
+        for (i, b) in basis.into_iter().enumerate() {
+            assert_eq!(m.row(i), b);
+            assert_eq!(m.column(i), b);
+        }
+    }
+
+    #[test]
+    fn the_last_column_of_a_translation_is_the_translated_origin() {
+        let m = Matrix4::translation(5.0, -3.0, 2.0);
+
+        assert_eq!(m.column(3), Tuple::new_point(5.0, -3.0, 2.0));
+        assert_eq!(m.row(0), Tuple::new(1.0, 0.0, 0.0, 5.0));
+    }
+
+    #[test]
+    fn the_third_row_of_a_view_transform_points_backwards() {
+        let from = Tuple::new_point(1.0, 3.0, 2.0);
+        let to = Tuple::new_point(4.0, -2.0, 8.0);
+        let up = Tuple::new_vector(1.0, 1.0, 0.0);
+        let t = Matrix4::view_transform(from, to, up);
+        let forward = (to - from).normalize();
+
+        assert_eq!(
+            t.row(2),
+            Tuple::new(-forward.x, -forward.y, -forward.z, t[2][3])
+        );
+    }
+
+    #[test]
+    fn flipping_handedness_mirrors_z() {
+        let m = Matrix4::flip_handedness();
+
+        assert_eq!(
+            m * Tuple::new_point(1.0, 2.0, 3.0),
+            Tuple::new_point(1.0, 2.0, -3.0)
+        );
+        assert_eq!(m * m, Matrix4::identity());
+        assert_eq!(m.determinant(), -1.0);
+    }
 }
@@ -0,0 +1,513 @@
+use crate::color::Color;
+use crate::group::Group;
+use crate::material::Material;
+use crate::triangle::Triangle;
+use crate::tuple::Tuple;
+
+// The geometry read from a Wavefront OBJ file. Faces before the first group statement go into the
+// default group. Lines that aren't understood are counted and skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjFile {
+    pub vertices: Vec<Tuple>,
+    pub default_group: Group,
+    pub groups: Vec<(String, Group)>,
+    // From the material libraries the file names, in the order they are defined.
+    pub materials: Vec<(String, Material)>,
+    pub ignored: usize,
+}
+
+impl ObjFile {
+    // OBJ files number their vertices from 1.
+    pub fn vertex(&self, index: usize) -> Tuple {
+        self.vertices[index - 1]
+    }
+
+    pub fn group_named(&self, name: &str) -> Option<&Group> {
+        self.groups
+            .iter()
+            .find(|(group_name, _)| group_name == name)
+            .map(|(_, group)| group)
+    }
+
+    // The default group's faces and one child group per named group, which keeps its name so it
+    // can be found and given its own material.
+    pub fn to_group(&self) -> Group {
+        let mut group = Group::new();
+        for child in self.default_group.children() {
+            group.add_child(child.clone());
+        }
+        for (_, child) in &self.groups {
+            group.add_child(child.clone());
+        }
+        group
+    }
+
+    // The vertices of a face record, or None if it isn't a polygon over known vertices. Only the
+    // vertex index of each v/vt/vn triplet is used.
+    fn face_vertices<'a>(&self, indices: impl Iterator<Item = &'a str>) -> Option<Vec<Tuple>> {
+        let vertices = indices
+            .map(|index| {
+                let index: usize = index.split('/').next()?.parse().ok()?;
+                (1..=self.vertices.len())
+                    .contains(&index)
+                    .then(|| self.vertex(index))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        (vertices.len() >= 3).then_some(vertices)
+    }
+}
+
+pub fn obj_to_group(obj: &ObjFile) -> Group {
+    obj.to_group()
+}
+
+// The first three numbers, for the statements that take an x, y and z or an r, g and b.
+fn parse_triple<'a>(words: impl Iterator<Item = &'a str>) -> Option<(f64, f64, f64)> {
+    let numbers = words
+        .take(3)
+        .map(|word| word.parse::<f64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    match numbers[..] {
+        [x, y, z] => Some((x, y, z)),
+        _ => None,
+    }
+}
+
+// The materials of a Wavefront MTL file. Kd is the color, Ks the strength and tint of the
+// highlight and Ns its shininess. Other statements are skipped.
+pub fn parse_mtl(input: &str) -> Vec<(String, Material)> {
+    let mut materials: Vec<(String, Material)> = Vec::new();
+
+    for line in input.lines() {
+        let mut words = line.split_whitespace();
+        match (words.next(), materials.last_mut()) {
+            (Some("newmtl"), _) => {
+                materials.push((words.collect::<Vec<_>>().join(" "), Material::new()));
+            }
+            (Some("Kd"), Some((_, material))) => {
+                if let Some((r, g, b)) = parse_triple(words) {
+                    material.color = Color::new(r, g, b);
+                }
+            }
+            (Some("Ks"), Some((_, material))) => {
+                if let Some((r, g, b)) = parse_triple(words) {
+                    // A gray highlight is only as strong as its brightest channel; any other
+                    // color also tints it.
+                    let peak = r.max(g).max(b);
+                    material.specular = peak;
+                    material.specular_color = (peak > 0.0 && (r != g || g != b))
+                        .then(|| Color::new(r, g, b) * (1.0 / peak));
+                }
+            }
+            (Some("Ns"), Some((_, material))) => {
+                if let Some(shininess) = words.next().and_then(|word| word.parse().ok()) {
+                    material.shininess = shininess;
+                }
+            }
+            _ => {}
+        }
+    }
+    materials
+}
+
+pub fn parse_obj(input: &str) -> ObjFile {
+    parse_obj_with(input, |_| None)
+}
+
+// Like parse_obj, but mtllib statements are followed: resolve gives the contents of a material
+// library by file name, or None if it can't be read. Faces after a usemtl statement get that
+// material.
+pub fn parse_obj_with(input: &str, resolve: impl Fn(&str) -> Option<String>) -> ObjFile {
+    let mut obj = ObjFile {
+        vertices: Vec::new(),
+        default_group: Group::new(),
+        groups: Vec::new(),
+        materials: Vec::new(),
+        ignored: 0,
+    };
+    let mut current_group: Option<usize> = None;
+    let mut current_material: Option<Material> = None;
+
+    for line in input.lines() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            None => {}
+            Some("v") => {
+                // Anything after x, y and z, such as a w or a vertex color, is left out.
+                match parse_triple(words) {
+                    Some((x, y, z)) => obj.vertices.push(Tuple::new_point(x, y, z)),
+                    None => obj.ignored += 1,
+                }
+            }
+            Some("f") => match obj.face_vertices(words) {
+                Some(vertices) => {
+                    let group = match current_group {
+                        Some(index) => &mut obj.groups[index].1,
+                        None => &mut obj.default_group,
+                    };
+                    // Polygons are split into a fan of triangles around their first vertex.
+                    for pair in vertices[1..].windows(2) {
+                        let mut triangle = Triangle::new(vertices[0], pair[0], pair[1]);
+                        if let Some(material) = &current_material {
+                            triangle.material = material.clone();
+                        }
+                        group.add_child(triangle);
+                    }
+                }
+                None => obj.ignored += 1,
+            },
+            Some("g") => {
+                let name = words.collect::<Vec<_>>().join(" ");
+                let index = match obj.groups.iter().position(|(n, _)| *n == name) {
+                    Some(index) => index,
+                    None => {
+                        let mut group = Group::new();
+                        group.name = Some(name.clone());
+                        obj.groups.push((name, group));
+                        obj.groups.len() - 1
+                    }
+                };
+                current_group = Some(index);
+            }
+            Some("mtllib") => {
+                let mut missing = false;
+                for file in words {
+                    match resolve(file) {
+                        Some(contents) => obj.materials.extend(parse_mtl(&contents)),
+                        None => missing = true,
+                    }
+                }
+                if missing {
+                    obj.ignored += 1;
+                }
+            }
+            Some("usemtl") => {
+                let name = words.collect::<Vec<_>>().join(" ");
+                // A later definition of the same name wins.
+                current_material = obj
+                    .materials
+                    .iter()
+                    .rev()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, material)| material.clone());
+                if current_material.is_none() {
+                    obj.ignored += 1;
+                }
+            }
+            Some(_) => obj.ignored += 1,
+        }
+    }
+    obj
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::group::Group;
+    use crate::material::Material;
+    use crate::obj::{obj_to_group, parse_mtl, parse_obj, parse_obj_with};
+    use crate::shape::Shape;
+    use crate::triangle::Triangle;
+    use crate::tuple::Tuple;
+    use crate::world::WorldShape;
+
+    fn triangles(group: &Group) -> Vec<&Triangle> {
+        group
+            .children()
+            .iter()
+            .map(|child| match child {
+                WorldShape::Triangle(triangle) => triangle,
+                _ => panic!("expected a triangle, found {:?}", child),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ignoring_unrecognized_lines() {
+        let gibberish = "There was a young lady named Bright
+who traveled much faster than light.
+She set out one day
+in a relative way,
+and came back the previous night.";
+        let obj = parse_obj(gibberish);
+
+        assert_eq!(obj.ignored, 5);
+        assert!(obj.vertices.is_empty());
+    }
+
+    #[test]
+    fn vertex_records() {
+        let file = "v -1 1 0
+v -1.0000 0.5000 0.0000
+v 1 0 0
+v 1 1 0";
+        let obj = parse_obj(file);
+
+        assert_eq!(obj.vertex(1), Tuple::new_point(-1.0, 1.0, 0.0));
+        assert_eq!(obj.vertex(2), Tuple::new_point(-1.0, 0.5, 0.0));
+        assert_eq!(obj.vertex(3), Tuple::new_point(1.0, 0.0, 0.0));
+        assert_eq!(obj.vertex(4), Tuple::new_point(1.0, 1.0, 0.0));
+        assert_eq!(obj.ignored, 0);
+    }
+
+    #[test]
+    fn vertex_records_with_extra_components() {
+        let file = "v 0 0 0 1
+v 1 0 0 1.0 0.5 0.25 0.125
+v 0 1 0
+v 0 0 1
+f 1 2 3";
+        let obj = parse_obj(file);
+        let t = triangles(&obj.default_group);
+
+        assert_eq!(obj.vertices.len(), 4);
+        assert_eq!(obj.vertex(1), Tuple::new_point(0.0, 0.0, 0.0));
+        assert_eq!(obj.vertex(2), Tuple::new_point(1.0, 0.0, 0.0));
+        assert_eq!(obj.ignored, 0);
+        assert_eq!(t[0].p1, obj.vertex(1));
+        assert_eq!(t[0].p2, obj.vertex(2));
+        assert_eq!(t[0].p3, obj.vertex(3));
+    }
+
+    #[test]
+    fn parsing_triangle_faces() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+f 1 2 3
+f 1 3 4";
+        let obj = parse_obj(file);
+        let t = triangles(&obj.default_group);
+
+        assert_eq!(t.len(), 2);
+        assert_eq!(t[0].p1, obj.vertex(1));
+        assert_eq!(t[0].p2, obj.vertex(2));
+        assert_eq!(t[0].p3, obj.vertex(3));
+        assert_eq!(t[1].p1, obj.vertex(1));
+        assert_eq!(t[1].p2, obj.vertex(3));
+        assert_eq!(t[1].p3, obj.vertex(4));
+    }
+
+    #[test]
+    fn triangulating_polygons() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+v 0 2 0
+
+f 1 2 3 4 5";
+        let obj = parse_obj(file);
+        let t = triangles(&obj.default_group);
+
+        assert_eq!(t.len(), 3);
+        assert_eq!(t[0].p1, obj.vertex(1));
+        assert_eq!(t[0].p2, obj.vertex(2));
+        assert_eq!(t[0].p3, obj.vertex(3));
+        assert_eq!(t[1].p1, obj.vertex(1));
+        assert_eq!(t[1].p2, obj.vertex(3));
+        assert_eq!(t[1].p3, obj.vertex(4));
+        assert_eq!(t[2].p1, obj.vertex(1));
+        assert_eq!(t[2].p2, obj.vertex(4));
+        assert_eq!(t[2].p3, obj.vertex(5));
+    }
+
+    #[test]
+    fn faces_with_bad_vertex_indices_are_ignored() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+f 1 2 4
+f 1 2
+f 1/1/1 2/2/2 3/3/3";
+        let obj = parse_obj(file);
+
+        assert_eq!(obj.ignored, 2);
+        assert_eq!(triangles(&obj.default_group).len(), 1);
+    }
+
+    #[test]
+    fn triangles_in_groups() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4";
+        let obj = parse_obj(file);
+        let t1 = triangles(obj.group_named("FirstGroup").unwrap());
+        let t2 = triangles(obj.group_named("SecondGroup").unwrap());
+
+        assert!(obj.default_group.children().is_empty());
+        assert_eq!(t1.len(), 1);
+        assert_eq!(t1[0].p3, obj.vertex(3));
+        assert_eq!(t2.len(), 1);
+        assert_eq!(t2[0].p3, obj.vertex(4));
+    }
+
+    #[test]
+    fn converting_an_obj_file_to_a_group() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+f 1 2 4
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4";
+        let obj = parse_obj(file);
+        let g = obj.to_group();
+
+        assert_eq!(obj_to_group(&obj), g);
+        assert_eq!(g.children().len(), 3);
+        assert!(matches!(g.children()[0], WorldShape::Triangle(_)));
+        for (child, name) in g.children()[1..].iter().zip(["FirstGroup", "SecondGroup"]) {
+            let WorldShape::Group(group) = child else {
+                panic!("expected a group, found {:?}", child);
+            };
+            let expected = triangles(obj.group_named(name).unwrap());
+            assert_eq!(triangles(group)[0].p3, expected[0].p3);
+            assert_eq!(child.name(), Some(name));
+        }
+    }
+
+    #[test]
+    fn named_groups_can_be_recolored_on_their_own() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+g body
+f 1 2 3
+f 1 3 4
+g handle
+f 2 3 4";
+        let obj = parse_obj(file);
+        let mut g = obj_to_group(&obj);
+        let mut red = Material::new();
+        red.color = Color::new(1.0, 0.0, 0.0);
+        let handle = g
+            .children()
+            .iter()
+            .position(|child| child.name() == Some("handle"))
+            .unwrap();
+        g.update_child(handle, |child| {
+            if let WorldShape::Group(group) = child {
+                group.set_material_recursive(red.clone());
+            }
+        });
+
+        assert_eq!(triangles(obj.group_named("body").unwrap()).len(), 2);
+        assert_eq!(triangles(obj.group_named("handle").unwrap()).len(), 1);
+        assert!(obj.group_named("spout").is_none());
+        for child in g.children() {
+            let WorldShape::Group(group) = child else {
+                panic!("expected a group, found {:?}", child);
+            };
+            let expected = if child.name() == Some("handle") {
+                Color::new(1.0, 0.0, 0.0)
+            } else {
+                Material::new().color
+            };
+            for triangle in triangles(group) {
+                assert_eq!(triangle.material.color, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn parsing_a_material_library() {
+        let file = "# two materials
+newmtl shiny red
+Kd 1 0 0
+Ks 0.5 0.5 0.5
+Ns 50
+illum 2
+map_Kd red.png
+
+newmtl gold
+Ks 1.0 0.8 0.4";
+        let materials = parse_mtl(file);
+
+        assert_eq!(materials.len(), 2);
+        let (name, red) = &materials[0];
+        assert_eq!(name, "shiny red");
+        assert_eq!(red.color, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(red.specular, 0.5);
+        assert_eq!(red.specular_color, None);
+        assert_eq!(red.shininess, 50.0);
+        let (name, gold) = &materials[1];
+        assert_eq!(name, "gold");
+        assert_eq!(gold.color, Material::new().color);
+        assert_eq!(gold.specular, 1.0);
+        assert_eq!(gold.specular_color, Some(Color::new(1.0, 0.8, 0.4)));
+    }
+
+    #[test]
+    fn usemtl_switches_the_material_of_the_faces_that_follow() {
+        let file = "mtllib colors.mtl
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+f 1 2 3
+usemtl red
+f 1 2 3
+g other
+f 1 3 4
+usemtl blue
+f 2 3 4
+usemtl missing
+f 1 2 4";
+        let library = "newmtl red
+Kd 1 0 0
+newmtl blue
+Kd 0 0 1";
+        let obj = parse_obj_with(file, |name| {
+            (name == "colors.mtl").then(|| library.to_string())
+        });
+        let colors = |group: &Group| {
+            triangles(group)
+                .iter()
+                .map(|triangle| triangle.material.color)
+                .collect::<Vec<_>>()
+        };
+        let white = Material::new().color;
+        let red = Color::new(1.0, 0.0, 0.0);
+        let blue = Color::new(0.0, 0.0, 1.0);
+
+        assert_eq!(obj.materials.len(), 2);
+        assert_eq!(colors(&obj.default_group), [white, red]);
+        assert_eq!(
+            colors(obj.group_named("other").unwrap()),
+            [red, blue, white]
+        );
+        assert_eq!(obj.ignored, 1);
+    }
+
+    #[test]
+    fn material_libraries_that_cannot_be_read_are_ignored() {
+        let file = "mtllib missing.mtl
+usemtl red
+v -1 1 0
+v -1 0 0
+v 1 0 0
+f 1 2 3";
+        let obj = parse_obj(file);
+
+        assert!(obj.materials.is_empty());
+        assert_eq!(obj.ignored, 2);
+        assert_eq!(
+            triangles(&obj.default_group)
+                .iter()
+                .map(|t| t.material.color)
+                .collect::<Vec<_>>(),
+            [Material::new().color]
+        );
+    }
+}
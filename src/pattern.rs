@@ -0,0 +1,519 @@
+use crate::color::Color;
+use crate::matrix::Matrix4;
+use crate::shape::Shape;
+use crate::tuple::Tuple;
+use std::any::Any;
+use std::fmt::Debug;
+
+// Kept object safe so materials can hold any pattern behind a pointer.
+pub trait Pattern: Debug + BoxedPattern {
+    fn transform(&self) -> &Matrix4;
+
+    fn transform_mut(&mut self) -> &mut Matrix4;
+
+    // The color at a point in pattern space.
+    fn pattern_at(&self, point: Tuple) -> Color;
+}
+
+// Lets boxed patterns be cloned and compared, so materials holding them still can be. Every
+// pattern that is Clone and PartialEq gets it for free.
+pub trait BoxedPattern {
+    fn box_clone(&self) -> Box<dyn Pattern>;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn box_eq(&self, other: &dyn Pattern) -> bool;
+}
+
+impl<P: Pattern + Clone + PartialEq + 'static> BoxedPattern for P {
+    fn box_clone(&self) -> Box<dyn Pattern> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn box_eq(&self, other: &dyn Pattern) -> bool {
+        other.as_any().downcast_ref::<P>() == Some(self)
+    }
+}
+
+impl Clone for Box<dyn Pattern> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+impl PartialEq for dyn Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.box_eq(other)
+    }
+}
+
+// Works around derived PartialEq failing to compare boxed trait objects, see
+// https://github.com/rust-lang/rust/issues/31740.
+impl PartialEq<&Self> for Box<dyn Pattern> {
+    fn eq(&self, other: &&Self) -> bool {
+        self.box_eq(other.as_ref())
+    }
+}
+
+// Blends from a at x = 0 to b at x = 1, then starts over.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradientPattern {
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+    pub transform: Matrix4,
+}
+
+impl GradientPattern {
+    pub fn new(a: Color, b: Color) -> Self {
+        Self::nested(SolidPattern::new(a), SolidPattern::new(b))
+    }
+
+    pub fn nested(a: impl Pattern + 'static, b: impl Pattern + 'static) -> Self {
+        Self {
+            a: Box::new(a),
+            b: Box::new(b),
+            transform: Matrix4::identity(),
+        }
+    }
+}
+
+impl Pattern for GradientPattern {
+    fn transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Matrix4 {
+        &mut self.transform
+    }
+
+    fn pattern_at(&self, point: Tuple) -> Color {
+        let a = child_at(self.a.as_ref(), point);
+        let b = child_at(self.b.as_ref(), point);
+        a + (b - a) * (point.x - point.x.floor())
+    }
+}
+
+// Concentric rings around the y axis, one unit wide.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RingPattern {
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+    pub transform: Matrix4,
+}
+
+impl RingPattern {
+    pub fn new(a: Color, b: Color) -> Self {
+        Self::nested(SolidPattern::new(a), SolidPattern::new(b))
+    }
+
+    pub fn nested(a: impl Pattern + 'static, b: impl Pattern + 'static) -> Self {
+        Self {
+            a: Box::new(a),
+            b: Box::new(b),
+            transform: Matrix4::identity(),
+        }
+    }
+}
+
+impl Pattern for RingPattern {
+    fn transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Matrix4 {
+        &mut self.transform
+    }
+
+    fn pattern_at(&self, point: Tuple) -> Color {
+        if (point.x.powi(2) + point.z.powi(2)).sqrt().floor() as i64 % 2 == 0 {
+            child_at(self.a.as_ref(), point)
+        } else {
+            child_at(self.b.as_ref(), point)
+        }
+    }
+}
+
+// Unit cubes alternating in all three dimensions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckerPattern {
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+    pub transform: Matrix4,
+}
+
+impl CheckerPattern {
+    pub fn new(a: Color, b: Color) -> Self {
+        Self::nested(SolidPattern::new(a), SolidPattern::new(b))
+    }
+
+    pub fn nested(a: impl Pattern + 'static, b: impl Pattern + 'static) -> Self {
+        Self {
+            a: Box::new(a),
+            b: Box::new(b),
+            transform: Matrix4::identity(),
+        }
+    }
+}
+
+impl Pattern for CheckerPattern {
+    fn transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Matrix4 {
+        &mut self.transform
+    }
+
+    fn pattern_at(&self, point: Tuple) -> Color {
+        if (point.x.floor() + point.y.floor() + point.z.floor()) as i64 % 2 == 0 {
+            child_at(self.a.as_ref(), point)
+        } else {
+            child_at(self.b.as_ref(), point)
+        }
+    }
+}
+
+// A nested pattern sees the point in its own space, so it can be moved and scaled within the
+// pattern holding it.
+fn child_at(child: &dyn Pattern, point: Tuple) -> Color {
+    child.pattern_at(child.transform().inverse() * point)
+}
+
+// The pattern's color at a point on the object, given in the space the object sits in, found by
+// moving the point into object space and then into pattern space.
+pub fn pattern_at_shape<S: Shape>(pattern: &dyn Pattern, object: &S, world_point: Tuple) -> Color {
+    let object_point = object.world_to_object(world_point);
+    let pattern_point = pattern.transform().inverse() * object_point;
+    pattern.pattern_at(pattern_point)
+}
+
+// A single color everywhere. The other patterns hold two of these unless they are given
+// patterns to nest.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SolidPattern {
+    pub color: Color,
+    pub transform: Matrix4,
+}
+
+impl SolidPattern {
+    pub fn new(color: Color) -> Self {
+        Self {
+            color,
+            transform: Matrix4::identity(),
+        }
+    }
+}
+
+impl Pattern for SolidPattern {
+    fn transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Matrix4 {
+        &mut self.transform
+    }
+
+    fn pattern_at(&self, _point: Tuple) -> Color {
+        self.color
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StripePattern {
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+    pub transform: Matrix4,
+}
+
+impl StripePattern {
+    pub fn new(a: Color, b: Color) -> Self {
+        Self::nested(SolidPattern::new(a), SolidPattern::new(b))
+    }
+
+    pub fn nested(a: impl Pattern + 'static, b: impl Pattern + 'static) -> Self {
+        Self {
+            a: Box::new(a),
+            b: Box::new(b),
+            transform: Matrix4::identity(),
+        }
+    }
+}
+
+impl Pattern for StripePattern {
+    fn transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Matrix4 {
+        &mut self.transform
+    }
+
+    fn pattern_at(&self, point: Tuple) -> Color {
+        if point.x.floor() as i64 % 2 == 0 {
+            child_at(self.a.as_ref(), point)
+        } else {
+            child_at(self.b.as_ref(), point)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::matrix::Matrix4;
+    use crate::pattern::{
+        pattern_at_shape, CheckerPattern, GradientPattern, Pattern, RingPattern, SolidPattern,
+        StripePattern,
+    };
+    use crate::sphere::Sphere;
+    use crate::tuple::Tuple;
+    use std::f64::consts::PI;
+
+    fn white() -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    fn black() -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    #[test]
+    fn creating_a_stripe_pattern() {
+        let pattern = StripePattern::new(white(), black());
+
+        assert_eq!(
+            pattern.a.pattern_at(Tuple::new_point(0.0, 0.0, 0.0)),
+            white()
+        );
+        assert_eq!(
+            pattern.b.pattern_at(Tuple::new_point(0.0, 0.0, 0.0)),
+            black()
+        );
+        assert_eq!(pattern.transform, Matrix4::identity());
+    }
+
+    #[test]
+    fn a_stripe_pattern_is_constant_in_y() {
+        let pattern = StripePattern::new(white(), black());
+
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 1.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 2.0, 0.0)), white());
+    }
+
+    #[test]
+    fn a_stripe_pattern_is_constant_in_z() {
+        let pattern = StripePattern::new(white(), black());
+
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 0.0, 1.0)), white());
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 0.0, 2.0)), white());
+    }
+
+    #[test]
+    fn a_stripe_pattern_alternates_in_x() {
+        let pattern = StripePattern::new(white(), black());
+
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.9, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(Tuple::new_point(1.0, 0.0, 0.0)), black());
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(-0.1, 0.0, 0.0)),
+            black()
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(-1.0, 0.0, 0.0)),
+            black()
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(-1.1, 0.0, 0.0)),
+            white()
+        );
+    }
+
+    #[test]
+    fn a_pattern_is_object_safe() {
+        let mut pattern: Box<dyn Pattern> = Box::new(StripePattern::new(white(), black()));
+        *pattern.transform_mut() = Matrix4::scaling(2.0, 2.0, 2.0);
+
+        assert_eq!(*pattern.transform(), Matrix4::scaling(2.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn stripes_with_an_object_transformation() {
+        let mut object = Sphere::new();
+        object.transform = Matrix4::scaling(2.0, 2.0, 2.0);
+        let pattern = StripePattern::new(white(), black());
+        let c = pattern_at_shape(&pattern, &object, Tuple::new_point(1.5, 0.0, 0.0));
+
+        assert_eq!(c, white());
+    }
+
+    #[test]
+    fn stripes_with_a_pattern_transformation() {
+        let object = Sphere::new();
+        let mut pattern = StripePattern::new(white(), black());
+        pattern.transform = Matrix4::scaling(2.0, 2.0, 2.0);
+        let c = pattern_at_shape(&pattern, &object, Tuple::new_point(1.5, 0.0, 0.0));
+
+        assert_eq!(c, white());
+    }
+
+    #[test]
+    fn stripes_with_both_an_object_and_a_pattern_transformation() {
+        let mut object = Sphere::new();
+        object.transform = Matrix4::scaling(2.0, 2.0, 2.0);
+        let mut pattern = StripePattern::new(white(), black());
+        pattern.transform = Matrix4::translation(0.5, 0.0, 0.0);
+        let c = pattern_at_shape(&pattern, &object, Tuple::new_point(2.5, 0.0, 0.0));
+
+        assert_eq!(c, white());
+    }
+
+    #[test]
+    fn boxed_patterns_compare_by_type_and_value() {
+        let stripes: Box<dyn Pattern> = Box::new(StripePattern::new(white(), black()));
+        let inverted: Box<dyn Pattern> = Box::new(StripePattern::new(black(), white()));
+
+        assert!(stripes == stripes.clone());
+        assert!(stripes != inverted);
+    }
+
+    #[test]
+    fn a_gradient_linearly_interpolates_between_colors() {
+        let pattern = GradientPattern::new(white(), black());
+
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 0.0, 0.0)), white());
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(0.25, 0.0, 0.0)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(0.5, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(0.75, 0.0, 0.0)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+    }
+
+    #[test]
+    fn a_ring_should_extend_in_both_x_and_z() {
+        let pattern = RingPattern::new(white(), black());
+
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(Tuple::new_point(1.0, 0.0, 0.0)), black());
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 0.0, 1.0)), black());
+        // 0.708 = just slightly more than sqrt(2)/2
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(0.708, 0.0, 0.708)),
+            black()
+        );
+        assert_eq!(pattern.pattern_at(Tuple::new_point(1.5, 0.0, 1.5)), white());
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_x() {
+        let pattern = CheckerPattern::new(white(), black());
+
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 0.0, 0.0)), white());
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(0.99, 0.0, 0.0)),
+            white()
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(1.01, 0.0, 0.0)),
+            black()
+        );
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_y() {
+        let pattern = CheckerPattern::new(white(), black());
+
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 0.0, 0.0)), white());
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(0.0, 0.99, 0.0)),
+            white()
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(0.0, 1.01, 0.0)),
+            black()
+        );
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_z() {
+        let pattern = CheckerPattern::new(white(), black());
+
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 0.0, 0.0)), white());
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(0.0, 0.0, 0.99)),
+            white()
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(0.0, 0.0, 1.01)),
+            black()
+        );
+    }
+
+    #[test]
+    fn checkers_flip_just_below_a_boundary() {
+        let pattern = CheckerPattern::new(white(), black());
+
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(-0.01, 0.0, 0.0)),
+            black()
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(-0.01, -0.01, 0.0)),
+            white()
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(1.99, 0.0, -0.01)),
+            white()
+        );
+    }
+
+    #[test]
+    fn a_solid_pattern_is_the_same_everywhere() {
+        let pattern = SolidPattern::new(white());
+
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 0.0, 0.0)), white());
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(-3.5, 2.0, 7.1)),
+            white()
+        );
+    }
+
+    #[test]
+    fn checkers_of_nested_stripes() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let blue = Color::new(0.0, 0.0, 1.0);
+        let mut along_x = StripePattern::new(white(), black());
+        along_x.transform = Matrix4::scaling(0.25, 0.25, 0.25);
+        let mut along_z = StripePattern::new(red, blue);
+        along_z.transform = Matrix4::rotation_y(-PI / 2.0).scale(0.5, 0.5, 0.5);
+        let mut pattern = CheckerPattern::nested(along_x, along_z);
+        pattern.transform = Matrix4::scaling(2.0, 2.0, 2.0);
+        let at = |x, z| {
+            let point = pattern.transform.inverse() * Tuple::new_point(x, 0.0, z);
+            pattern.pattern_at(point)
+        };
+
+        // The stripes are transformed within the checker space, which is twice the size.
+        // The first cell holds stripes half a unit wide along x.
+        assert_eq!(at(0.25, 0.25), white());
+        assert_eq!(at(0.75, 0.25), black());
+        assert_eq!(at(1.25, 0.25), white());
+        // The next cell holds stripes one unit wide that change along z.
+        assert_eq!(at(2.5, 0.5), red);
+        assert_eq!(at(2.5, 1.5), blue);
+        assert_eq!(at(3.5, 1.5), blue);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::bounds::Bounds;
 use crate::intersections::{Intersection, Intersections};
 use crate::material::Material;
 use crate::matrix::Matrix4;
@@ -6,10 +7,13 @@ use crate::shape::Shape;
 use crate::tuple::Tuple;
 use crate::EPSILON;
 
-#[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct Plane {
     pub transform: Matrix4,
     pub material: Material,
+    pub clip: Option<(Tuple, Tuple)>,
+    pub minimum_z: Option<f64>,
+    pub name: Option<String>,
 }
 
 impl Plane {
@@ -17,6 +21,9 @@ impl Plane {
         Self {
             transform: Matrix4::identity(),
             material: Material::new(),
+            clip: None,
+            minimum_z: None,
+            name: None,
         }
     }
 }
@@ -38,17 +45,53 @@ impl Shape for Plane {
         &mut self.transform
     }
 
-    fn local_intersect(&self, local_ray: Ray) -> Intersections<Self> {
+    fn local_intersect(&self, local_ray: Ray) -> Intersections<'_, Self> {
         if local_ray.direction.y.abs() < EPSILON {
-            return Intersections::new(Vec::new());
+            return Intersections::new_unsorted(Vec::new());
         }
         let t = -local_ray.origin.y / local_ray.direction.y;
-        Intersections::new(vec![Intersection::new(t, self)])
+        if let Some(minimum_z) = self.minimum_z {
+            if local_ray.position(t).z < minimum_z {
+                return Intersections::new_unsorted(Vec::new());
+            }
+        }
+        Intersections::new_unsorted(vec![Intersection::new(t, self)])
     }
 
     fn local_normal_at(&self, _local_point: Tuple) -> Tuple {
         Tuple::new_vector(0.0, 1.0, 0.0)
     }
+
+    fn local_closest_point(&self, local_point: Tuple) -> Option<Tuple> {
+        let z = match self.minimum_z {
+            Some(minimum_z) => local_point.z.max(minimum_z),
+            None => local_point.z,
+        };
+        Some(Tuple::new_point(local_point.x, 0.0, z))
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn set_name(&mut self, name: impl Into<String>) {
+        self.name = Some(name.into());
+    }
+
+    fn clip(&self) -> Option<(Tuple, Tuple)> {
+        self.clip
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::new(
+            Tuple::new_point(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
+            Tuple::new_point(f64::INFINITY, 0.0, f64::INFINITY),
+        )
+    }
+
+    fn max_intersections(&self) -> usize {
+        1
+    }
 }
 
 #[cfg(test)]
@@ -96,6 +139,13 @@ mod tests {
         assert!(xs.is_empty());
     }
 
+    #[test]
+    fn a_plane_reports_at_most_one_intersection() {
+        let p = Plane::new();
+
+        assert_eq!(p.max_intersections(), 1);
+    }
+
     #[test]
     fn a_ray_intersecting_a_plane_from_above() {
         let p = Plane::new();
@@ -123,4 +173,59 @@ mod tests {
         assert_float_eq!(xs[0].t, 1.0);
         assert!(ptr::eq(xs[0].object, &p));
     }
+
+    #[test]
+    fn a_plane_is_unbounded_by_default() {
+        let p = Plane::new();
+
+        assert_eq!(p.minimum_z, None);
+    }
+
+    #[test]
+    fn a_half_plane_rejects_hits_below_its_minimum_z() {
+        let mut p = Plane::new();
+        p.minimum_z = Some(2.0);
+        let rejected = Ray::new(
+            Tuple::new_point(0.0, 1.0, 1.0),
+            Tuple::new_vector(0.0, -1.0, 0.0),
+        );
+        let allowed = Ray::new(
+            Tuple::new_point(0.0, 1.0, 3.0),
+            Tuple::new_vector(0.0, -1.0, 0.0),
+        );
+
+        assert!(p.local_intersect(rejected).is_empty());
+        let xs = p.local_intersect(allowed);
+        assert_eq!(xs.len(), 1);
+        assert_float_eq!(xs[0].t, 1.0);
+    }
+
+    #[test]
+    fn an_infinite_plane_cannot_be_sampled() {
+        let p = Plane::new();
+
+        assert_eq!(p.sample_surface(0.5, 0.5), None);
+        assert_eq!(p.sample_surface_pdf(Tuple::new_point(0.0, 0.0, 0.0)), None);
+    }
+
+    #[test]
+    fn the_closest_point_on_the_xz_plane() {
+        let p = Plane::new();
+
+        assert_eq!(
+            p.closest_point(Tuple::new_point(3.0, 4.0, 5.0)),
+            Some(Tuple::new_point(3.0, 0.0, 5.0))
+        );
+    }
+
+    #[test]
+    fn the_closest_point_on_a_half_plane_stays_on_the_half_plane() {
+        let mut p = Plane::new();
+        p.minimum_z = Some(2.0);
+
+        assert_eq!(
+            p.closest_point(Tuple::new_point(3.0, -4.0, 0.0)),
+            Some(Tuple::new_point(3.0, 0.0, 2.0))
+        );
+    }
 }
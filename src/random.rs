@@ -0,0 +1,64 @@
+// A small xorshift generator. It is not suitable for anything but sampling, but it is fast,
+// deterministic for a given seed and needs no dependencies.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        // The state must never be zero, and nearby seeds should not give similar sequences, so
+        // the seed is scrambled with splitmix64 first.
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        Self { state: z.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    // Uniform in [0, 1), from the top 53 bits.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::XorShift;
+
+    #[test]
+    fn the_same_seed_gives_the_same_sequence() {
+        let mut a = XorShift::new(42);
+        let mut b = XorShift::new(42);
+
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn a_zero_seed_still_produces_numbers() {
+        let mut rng = XorShift::new(0);
+
+        assert_ne!(rng.next_u64(), rng.next_u64());
+    }
+
+    #[test]
+    fn samples_are_uniform_in_the_unit_interval() {
+        let mut rng = XorShift::new(7);
+        let samples: Vec<_> = (0..10000).map(|_| rng.next_f64()).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+
+        assert!(samples.iter().all(|&x| (0.0..1.0).contains(&x)));
+        assert!((mean - 0.5).abs() < 0.01);
+    }
+}
@@ -0,0 +1,69 @@
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::light::PointLight;
+use crate::matrix::Matrix4;
+use crate::sphere::Sphere;
+use crate::tuple::Tuple;
+use crate::world::World;
+use std::f64::consts::PI;
+
+// The three spheres, light and camera shared by the chapter 7 to 9 scenes. Callers add their own
+// floor and walls.
+pub fn three_spheres() -> (Camera, World) {
+    let mut middle = Sphere::new();
+    middle.transform = middle.transform.translate(-0.5, 1.0, 0.5);
+    middle.material.color = Color::new(0.1, 1.0, 0.5);
+    middle.material.diffuse = 0.7;
+    middle.material.specular = 0.3;
+
+    let mut right = Sphere::new();
+    right.transform = right
+        .transform
+        .scale(0.5, 0.5, 0.5)
+        .translate(1.5, 0.5, -0.5);
+    right.material.color = Color::new(0.5, 1.0, 0.1);
+    right.material.diffuse = 0.7;
+    right.material.specular = 0.3;
+
+    let mut left = Sphere::new();
+    left.transform = left
+        .transform
+        .scale(0.33, 0.33, 0.33)
+        .translate(-1.5, 0.33, -0.75);
+    left.material.color = Color::new(1.0, 0.8, 0.1);
+    left.material.diffuse = 0.7;
+    left.material.specular = 0.3;
+
+    let mut world = World::new();
+    world.objects.push(middle.into());
+    world.objects.push(right.into());
+    world.objects.push(left.into());
+    world.light = Some(PointLight::new(
+        Tuple::new_point(-10.0, 10.0, -10.0),
+        Color::new(1.0, 1.0, 1.0),
+    ));
+
+    let mut camera = Camera::new(100, 50, PI / 3.0);
+    camera.transform = Matrix4::view_transform(
+        Tuple::new_point(0.0, 1.5, -5.0),
+        Tuple::new_point(0.0, 1.0, 0.0),
+        Tuple::new_vector(0.0, 1.0, 0.0),
+    );
+
+    (camera, world)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scenes::three_spheres;
+
+    #[test]
+    fn the_three_spheres_scene() {
+        let (camera, world) = three_spheres();
+
+        assert_eq!(world.objects.len(), 3);
+        assert!(world.light.is_some());
+        assert_eq!(camera.hsize, 100);
+        assert_eq!(camera.vsize, 50);
+    }
+}
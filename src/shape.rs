@@ -1,8 +1,11 @@
+use crate::bounds::Bounds;
 use crate::intersections::Intersections;
 use crate::material::Material;
 use crate::matrix::Matrix4;
 use crate::ray::Ray;
 use crate::tuple::Tuple;
+use crate::EPSILON;
+use std::ptr;
 
 pub trait Shape
 where
@@ -16,40 +19,222 @@ where
 
     fn transform_mut(&mut self) -> &mut Matrix4;
 
-    fn local_intersect(&self, local_ray: Ray) -> Intersections<Self>;
+    // A name to find the shape by, such as one read from a scene or model file.
+    fn name(&self) -> Option<&str>;
+
+    fn set_name(&mut self, name: impl Into<String>);
+
+    fn set_material(&mut self, material: Material) {
+        *self.material_mut() = material;
+    }
+
+    fn set_transform(&mut self, transform: Matrix4) {
+        *self.transform_mut() = transform;
+    }
+
+    fn local_intersect(&self, local_ray: Ray) -> Intersections<'_, Self>;
 
     fn local_normal_at(&self, local_point: Tuple) -> Tuple;
 
-    fn intersect(&self, ray: Ray) -> Intersections<Self> {
+    // Whether local_normal_at promises unit vectors, so debug builds can check it does. Shapes
+    // that leave normalizing to normal_at say so here.
+    fn local_normals_are_unit(&self) -> bool {
+        true
+    }
+
+    fn max_intersections(&self) -> usize;
+
+    // Whether anything in the shape can block a shadow ray. Shapes that hold others answer for
+    // their children.
+    fn casts_shadow(&self) -> bool {
+        self.material().cast_shadow
+    }
+
+    // An object-space plane, given as a point and a normal. Only the part of the shape on the
+    // side the normal points to is kept.
+    fn clip(&self) -> Option<(Tuple, Tuple)> {
+        None
+    }
+
+    // An object-space point on the surface for u and v in [0, 1], spread evenly over the area.
+    // Shapes with an infinite surface return None.
+    fn local_sample_surface(&self, _u: f64, _v: f64) -> Option<Tuple> {
+        None
+    }
+
+    fn sample_surface(&self, u: f64, v: f64) -> Option<(Tuple, Tuple)> {
+        let point = *self.transform() * self.local_sample_surface(u, v)?;
+        Some((point, self.normal_at(point)))
+    }
+
+    // The object-space area local_sample_surface spreads its points over.
+    fn local_surface_area(&self) -> Option<f64> {
+        None
+    }
+
+    // How likely sample_surface is to pick a point near the given one, per unit of world-space
+    // area. A small patch of surface grows by the determinant of the transform divided by how
+    // much the inverse transpose shrinks its normal.
+    fn sample_surface_pdf(&self, point: Tuple) -> Option<f64> {
+        let area = self.local_surface_area()?;
+        let inverse = self.transform().inverse();
+        let local_normal = self.local_normal_at(inverse * point).normalize();
+        let stretch = inverse
+            .transpose()
+            .transform_vector(local_normal)
+            .magnitude();
+        Some(1.0 / (area * self.transform().determinant().abs() * stretch))
+    }
+
+    // An object-space box around the shape. Shapes that don't know their extent are never culled.
+    fn local_bounds(&self) -> Bounds {
+        Bounds::infinite()
+    }
+
+    fn parent_space_bounds(&self) -> Bounds {
+        self.local_bounds().transform(*self.transform())
+    }
+
+    fn local_closest_point(&self, _local_point: Tuple) -> Option<Tuple> {
+        None
+    }
+
+    // Found in object space, so it is exact for transforms made of rotations, translations and
+    // uniform scaling. The clip plane is ignored.
+    fn closest_point(&self, point: Tuple) -> Option<Tuple> {
+        let local_point = self.world_to_object(point);
+        Some(*self.transform() * self.local_closest_point(local_point)?)
+    }
+
+    // Whether the object is this shape or one of the shapes inside it.
+    fn includes(&self, object: &Self) -> bool {
+        ptr::eq(self, object)
+    }
+
+    // Calls f with each shape whose surface can be sampled on its own, along with the transforms
+    // of the groups between it and this shape, starting from parent_transform. Shapes that hold
+    // others pass on their children instead of themselves.
+    fn visit_surfaces<'a>(
+        &'a self,
+        parent_transform: Matrix4,
+        f: &mut dyn FnMut(&'a Self, Matrix4),
+    ) {
+        f(self, parent_transform);
+    }
+
+    fn intersect(&self, ray: Ray) -> Intersections<'_, Self> {
         let local_ray = ray.transform(self.transform().inverse());
-        self.local_intersect(local_ray)
+        let xs = self.local_intersect(local_ray);
+        match self.clip() {
+            Some((point, normal)) => Intersections::new_unsorted(
+                xs.into_vec()
+                    .into_iter()
+                    .filter(|x| (local_ray.position(x.t) - point) * normal >= 0.0)
+                    .collect(),
+            ),
+            None => xs,
+        }
+    }
+
+    fn world_to_object(&self, point: Tuple) -> Tuple {
+        self.transform().inverse() * point
+    }
+
+    fn normal_to_world(&self, normal: Tuple) -> Tuple {
+        transform_normal(*self.transform(), normal)
     }
 
     fn normal_at(&self, point: Tuple) -> Tuple {
-        let local_point = self.transform().inverse() * point;
+        let local_point = self.world_to_object(point);
         let local_normal = self.local_normal_at(local_point);
-        let mut world_normal = self.transform().inverse().transpose() * local_normal;
-        world_normal.w = 0.0;
-        world_normal.normalize()
+        debug_assert!(
+            !self.local_normals_are_unit() || (local_normal.magnitude() - 1.0).abs() < EPSILON,
+            "{} {:?} returned a local normal of length {} at {:?}",
+            std::any::type_name::<Self>(),
+            self.name(),
+            local_normal.magnitude(),
+            local_point
+        );
+        let normal = self.normal_to_world(local_normal);
+        debug_assert!(
+            (normal.magnitude() - 1.0).abs() < EPSILON,
+            "{} returned a degenerate normal {:?} at {:?}",
+            std::any::type_name::<Self>(),
+            local_normal,
+            local_point
+        );
+        normal
+    }
+
+    // For a shape inside groups, whose transforms combine to parent_transform, the normal at a
+    // point in world space.
+    fn normal_at_within(&self, parent_transform: Matrix4, point: Tuple) -> Tuple {
+        let normal = self.normal_at(parent_transform.inverse() * point);
+        transform_normal(parent_transform, normal)
+    }
+
+    // closest_point for a shape inside groups, with the same limits on the transforms.
+    fn closest_point_within(&self, parent_transform: Matrix4, point: Tuple) -> Option<Tuple> {
+        let parent_point = parent_transform.inverse() * point;
+        Some(parent_transform * self.closest_point(parent_point)?)
+    }
+
+    // sample_surface for a shape inside groups, with the point and normal in world space.
+    fn sample_surface_within(
+        &self,
+        parent_transform: Matrix4,
+        u: f64,
+        v: f64,
+    ) -> Option<(Tuple, Tuple)> {
+        let (point, normal) = self.sample_surface(u, v)?;
+        Some((
+            parent_transform * point,
+            transform_normal(parent_transform, normal),
+        ))
+    }
+
+    // The group transforms stretch the area around the point the same way the shape's own
+    // transform does in sample_surface_pdf.
+    fn sample_surface_pdf_within(&self, parent_transform: Matrix4, point: Tuple) -> Option<f64> {
+        let inverse = parent_transform.inverse();
+        let parent_point = inverse * point;
+        let pdf = self.sample_surface_pdf(parent_point)?;
+        let stretch = inverse
+            .transpose()
+            .transform_vector(self.normal_at(parent_point))
+            .magnitude();
+        Some(pdf / (parent_transform.determinant().abs() * stretch))
     }
 }
 
+// The inverse transpose keeps the normal perpendicular to the transformed surface.
+fn transform_normal(transform: Matrix4, normal: Tuple) -> Tuple {
+    let mut normal = transform.inverse().transpose() * normal;
+    normal.w = 0.0;
+    normal.normalize()
+}
+
 #[cfg(test)]
 mod tests {
     use crate::intersections::Intersections;
     use crate::material::Material;
     use crate::matrix::Matrix4;
+    use crate::plane::Plane;
     use crate::ray::Ray;
     use crate::shape::Shape;
+    use crate::sphere::Sphere;
     use crate::tuple::Tuple;
+    use crate::world::WorldShape;
     use std::cell::Cell;
-    use std::f64::consts::PI;
+    use std::f64::consts::{FRAC_1_SQRT_2, PI};
 
     #[derive(Debug, Clone)]
     struct TestShape {
         transform: Matrix4,
         material: Material,
         saved_ray: Cell<Option<Ray>>,
+        name: Option<String>,
+        unit_normals: bool,
     }
 
     impl TestShape {
@@ -58,6 +243,8 @@ mod tests {
                 transform: Matrix4::identity(),
                 material: Material::new(),
                 saved_ray: Cell::new(None),
+                name: None,
+                unit_normals: false,
             }
         }
     }
@@ -79,7 +266,15 @@ mod tests {
             &mut self.transform
         }
 
-        fn local_intersect(&self, local_ray: Ray) -> Intersections<Self> {
+        fn name(&self) -> Option<&str> {
+            self.name.as_deref()
+        }
+
+        fn set_name(&mut self, name: impl Into<String>) {
+            self.name = Some(name.into());
+        }
+
+        fn local_intersect(&self, local_ray: Ray) -> Intersections<'_, Self> {
             self.saved_ray.set(Some(local_ray));
 
             Intersections::new(Vec::new())
@@ -88,12 +283,63 @@ mod tests {
         fn local_normal_at(&self, local_point: Tuple) -> Tuple {
             Tuple::new_vector(local_point.x, local_point.y, local_point.z)
         }
+
+        // The book's tests rely on normal_at normalizing whatever this returns.
+        fn local_normals_are_unit(&self) -> bool {
+            self.unit_normals
+        }
+
+        fn max_intersections(&self) -> usize {
+            0
+        }
     }
 
     fn test_shape() -> TestShape {
         TestShape::new()
     }
 
+    fn configure<S: Shape>(shape: &mut S) {
+        let mut m = Material::new();
+        m.ambient = 1.0;
+        shape.set_material(m);
+        shape.set_transform(Matrix4::translation(2.0, 3.0, 4.0));
+    }
+
+    #[test]
+    fn configuring_any_shape_through_the_trait() {
+        let mut shapes: Vec<WorldShape> = vec![Sphere::new().into(), Plane::new().into()];
+        let mut s = test_shape();
+        configure(&mut s);
+        for shape in shapes.iter_mut() {
+            configure(shape);
+        }
+
+        assert_eq!(*s.transform(), Matrix4::translation(2.0, 3.0, 4.0));
+        assert_eq!(s.material().ambient, 1.0);
+        for shape in shapes {
+            assert_eq!(*shape.transform(), Matrix4::translation(2.0, 3.0, 4.0));
+            assert_eq!(shape.material().ambient, 1.0);
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "TestShape Some(\"broken\") returned a local normal of length 2")]
+    fn a_shape_returning_a_length_2_normal_trips_the_debug_check() {
+        let mut s = test_shape();
+        s.unit_normals = true;
+        s.set_name("broken");
+        s.normal_at(Tuple::new_point(0.0, 2.0, 0.0));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "TestShape returned a degenerate normal")]
+    fn a_shape_returning_a_zero_normal_trips_the_debug_check() {
+        let s = test_shape();
+        s.normal_at(Tuple::new_point(0.0, 0.0, 0.0));
+    }
+
     #[test]
     fn the_default_transformation() {
         let s = test_shape();
@@ -121,7 +367,7 @@ mod tests {
         let mut s = test_shape();
         let mut m = Material::new();
         m.ambient = 1.0;
-        *s.material_mut() = m;
+        *s.material_mut() = m.clone();
 
         assert_eq!(*s.material_mut(), m);
     }
@@ -168,9 +414,9 @@ mod tests {
     fn computing_the_normal_on_a_translated_shape() {
         let mut s = test_shape();
         *s.transform_mut() = Matrix4::translation(0.0, 1.0, 0.0);
-        let n = s.normal_at(Tuple::new_point(0.0, 1.70711, -0.70711));
+        let n = s.normal_at(Tuple::new_point(0.0, 1.0 + FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
 
-        assert_eq!(n, Tuple::new_vector(0.0, 0.70711, -0.70711));
+        assert_eq!(n, Tuple::new_vector(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
     }
 
     #[test]
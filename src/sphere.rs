@@ -1,14 +1,31 @@
+use crate::bounds::Bounds;
+use crate::camera::Camera;
 use crate::intersections::{Intersection, Intersections};
 use crate::material::Material;
+use crate::math::solve_quadratic;
 use crate::matrix::Matrix4;
 use crate::ray::Ray;
 use crate::shape::Shape;
 use crate::tuple::Tuple;
+use std::f64::consts::PI;
 
-#[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct Sphere {
     pub transform: Matrix4,
     pub material: Material,
+    pub clip: Option<(Tuple, Tuple)>,
+    pub name: Option<String>,
+}
+
+// An ellipse in canvas pixel coordinates, where (0, 0) is the top left corner of the top left
+// pixel. The rotation is the angle from the x axis to the radius_x axis, in radians.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ellipse {
+    pub center_x: f64,
+    pub center_y: f64,
+    pub radius_x: f64,
+    pub radius_y: f64,
+    pub rotation: f64,
 }
 
 impl Sphere {
@@ -16,7 +33,76 @@ impl Sphere {
         Self {
             transform: Matrix4::identity(),
             material: Material::new(),
+            clip: None,
+            name: None,
+        }
+    }
+
+    // Projects the sphere's dual quadric through the pinhole to get the dual of its outline
+    // conic. Returns None unless the sphere lies entirely in front of the camera.
+    pub fn screen_silhouette(&self, camera: &Camera) -> Option<Ellipse> {
+        let m = camera.transform * self.transform;
+        if (m * Tuple::new_point(0.0, 0.0, 0.0)).z >= 0.0 {
+            return None;
         }
+        let d = Matrix4::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, -1.0],
+        ]);
+        let q = m * d * m.transpose();
+        // The image plane sits at z = -1, so (x, y, z) projects to the homogeneous (x, y, -z).
+        let sign = [1.0, 1.0, -1.0];
+        let dual = |i: usize, j: usize| sign[i] * sign[j] * q[i][j];
+        let w = dual(2, 2);
+        if w >= 0.0 {
+            return None;
+        }
+        let (cu, cv) = (dual(0, 2) / w, dual(1, 2) / w);
+        let scale = camera.pixel_size.powi(2);
+        let a = (cu * cu - dual(0, 0) / w) / scale;
+        let b = (cu * cv - dual(0, 1) / w) / scale;
+        let c = (cv * cv - dual(1, 1) / w) / scale;
+        let mean = (a + c) / 2.0;
+        let spread = f64::sqrt(((a - c) / 2.0).powi(2) + b.powi(2));
+        if mean - spread <= 0.0 {
+            return None;
+        }
+        Some(Ellipse {
+            center_x: (camera.half_width - cu) / camera.pixel_size,
+            center_y: (camera.half_height - cv) / camera.pixel_size,
+            radius_x: f64::sqrt(mean + spread),
+            radius_y: f64::sqrt(mean - spread),
+            rotation: 0.5 * f64::atan2(2.0 * b, a - c),
+        })
+    }
+
+    // The intersections of a ray with the union of this sphere and another, worked out from the
+    // spans of the ray inside each rather than by CSG, so it can check CSG. Clipping is ignored.
+    pub fn intersect_pair<'a>(&'a self, other: &'a Sphere, r: Ray) -> Intersections<'a, Sphere> {
+        let span = |sphere: &'a Sphere| {
+            let xs = sphere.local_intersect(r.transform(sphere.transform.inverse()));
+            (xs.len() == 2).then(|| (xs[0].t.min(xs[1].t), xs[1].t.max(xs[0].t), sphere))
+        };
+        let xs = match (span(self), span(other)) {
+            (Some((t0, t1, a)), Some((t2, t3, b))) if t0 <= t3 && t2 <= t1 => {
+                let first = if t0 <= t2 { a } else { b };
+                let last = if t1 >= t3 { a } else { b };
+                vec![
+                    Intersection::new(t0.min(t2), first),
+                    Intersection::new(t1.max(t3), last),
+                ]
+            }
+            (a, b) => a
+                .into_iter()
+                .chain(b)
+                .flat_map(|(t0, t1, sphere)| {
+                    [Intersection::new(t0, sphere), Intersection::new(t1, sphere)]
+                })
+                .collect(),
+        };
+        Intersections::new(xs)
     }
 }
 
@@ -37,37 +123,83 @@ impl Shape for Sphere {
         &mut self.transform
     }
 
-    fn local_intersect(&self, local_ray: Ray) -> Intersections<Self> {
+    fn local_intersect(&self, local_ray: Ray) -> Intersections<'_, Self> {
         let sphere_to_ray = local_ray.origin - Tuple::new_point(0.0, 0.0, 0.0);
         let a = local_ray.direction * local_ray.direction;
         let b = 2.0 * (local_ray.direction * sphere_to_ray);
         let c = (sphere_to_ray * sphere_to_ray) - 1.0;
 
-        let discriminant = b.powi(2) - 4.0 * a * c;
-        if discriminant < 0.0 {
-            Intersections::new(Vec::new())
-        } else {
-            Intersections::new(vec![
-                Intersection::new((-b - discriminant.sqrt()) / (2.0 * a), self),
-                Intersection::new((-b + discriminant.sqrt()) / (2.0 * a), self),
-            ])
+        match solve_quadratic(a, b, c) {
+            Some((t0, t1)) => Intersections::new_unsorted(vec![
+                Intersection::new(t0, self),
+                Intersection::new(t1, self),
+            ]),
+            None => Intersections::new_unsorted(Vec::new()),
         }
     }
 
+    // Normalized, since points a little off the surface are asked about too.
     fn local_normal_at(&self, local_point: Tuple) -> Tuple {
-        local_point - Tuple::new_point(0.0, 0.0, 0.0)
+        (local_point - Tuple::new_point(0.0, 0.0, 0.0)).normalize()
+    }
+
+    // Archimedes: equal slices of y cut equal areas from the sphere.
+    fn local_sample_surface(&self, u: f64, v: f64) -> Option<Tuple> {
+        let y = 1.0 - 2.0 * v;
+        let radius = f64::sqrt(1.0 - y * y);
+        let phi = 2.0 * PI * u;
+        Some(Tuple::new_point(radius * phi.cos(), y, radius * phi.sin()))
+    }
+
+    fn local_surface_area(&self) -> Option<f64> {
+        Some(4.0 * PI)
+    }
+
+    fn local_closest_point(&self, local_point: Tuple) -> Option<Tuple> {
+        let from_center = local_point - Tuple::new_point(0.0, 0.0, 0.0);
+        if from_center.magnitude() == 0.0 {
+            return Some(Tuple::new_point(0.0, 1.0, 0.0));
+        }
+        Some(Tuple::new_point(0.0, 0.0, 0.0) + from_center.normalize())
+    }
+
+    fn clip(&self) -> Option<(Tuple, Tuple)> {
+        self.clip
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn set_name(&mut self, name: impl Into<String>) {
+        self.name = Some(name.into());
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::new(
+            Tuple::new_point(-1.0, -1.0, -1.0),
+            Tuple::new_point(1.0, 1.0, 1.0),
+        )
+    }
+
+    fn max_intersections(&self) -> usize {
+        2
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::assert_float_eq;
+    use crate::camera::Camera;
+    use crate::csg::{Csg, Operation};
     use crate::material::Material;
     use crate::matrix::Matrix4;
+    use crate::random::XorShift;
     use crate::ray::Ray;
     use crate::shape::Shape;
     use crate::sphere::Sphere;
     use crate::tuple::Tuple;
+    use std::f64::consts::PI;
     use std::ptr;
 
     #[test]
@@ -147,6 +279,53 @@ mod tests {
         assert!(ptr::eq(xs[1].object, &s));
     }
 
+    #[test]
+    fn a_sphere_reports_at_most_two_intersections() {
+        let s = Sphere::new();
+
+        assert_eq!(s.max_intersections(), 2);
+    }
+
+    #[test]
+    fn clipping_a_sphere_removes_intersections_on_the_culled_side() {
+        let mut s = Sphere::new();
+        s.clip = Some((
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        ));
+        let r = Ray::new(
+            Tuple::new_point(0.0, -5.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let xs = s.intersect(r);
+
+        assert_eq!(xs.len(), 1);
+        assert_float_eq!(xs[0].t, 6.0);
+
+        let r = Ray::new(
+            Tuple::new_point(0.0, -0.5, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+
+        assert!(s.intersect(r).is_empty());
+    }
+
+    #[test]
+    fn the_clip_plane_is_given_in_object_space() {
+        let mut s = Sphere::new();
+        s.transform = Matrix4::translation(0.0, 3.0, 0.0);
+        s.clip = Some((
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        ));
+        let r = Ray::new(
+            Tuple::new_point(0.0, 3.5, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+
+        assert_eq!(s.intersect(r).len(), 2);
+    }
+
     #[test]
     fn a_spheres_default_transformation() {
         let s = Sphere::new();
@@ -219,6 +398,35 @@ mod tests {
         assert_eq!(n, n.normalize());
     }
 
+    #[test]
+    fn the_normal_on_a_mirrored_sphere_points_outward() {
+        let mut s = Sphere::new();
+        s.transform = Matrix4::scaling(-1.0, 1.0, 1.0);
+
+        assert!(s.transform.determinant() < 0.0);
+        for p in [
+            Tuple::new_point(1.0, 0.0, 0.0),
+            Tuple::new_point(-1.0, 0.0, 0.0),
+            Tuple::new_point(
+                f64::sqrt(3.0) / 3.0,
+                -f64::sqrt(3.0) / 3.0,
+                f64::sqrt(3.0) / 3.0,
+            ),
+        ] {
+            let n = s.normal_at(p);
+            assert_eq!(n, Tuple::new_vector(p.x, p.y, p.z));
+        }
+    }
+
+    #[test]
+    fn the_normal_on_a_mirrored_and_scaled_sphere_points_outward() {
+        let mut s = Sphere::new();
+        s.transform = Matrix4::scaling(-2.0, 1.0, 1.0).translate(0.0, 3.0, 0.0);
+        let n = s.normal_at(Tuple::new_point(2.0, 3.0, 0.0));
+
+        assert_eq!(n, Tuple::new_vector(1.0, 0.0, 0.0));
+    }
+
     #[test]
     fn a_sphere_has_a_default_material() {
         let s = Sphere::new();
@@ -232,8 +440,256 @@ mod tests {
         let mut s = Sphere::new();
         let mut m = Material::new();
         m.ambient = 1.0;
-        s.material = m;
+        s.material = m.clone();
 
         assert_eq!(s.material, m);
     }
+
+    fn camera_looking_at_the_origin() -> Camera {
+        let mut c = Camera::new(101, 101, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        c
+    }
+
+    #[test]
+    fn the_silhouette_of_a_sphere_on_the_optical_axis_is_a_centered_circle() {
+        let c = camera_looking_at_the_origin();
+        let s = Sphere::new();
+        let e = s.screen_silhouette(&c).unwrap();
+        let expected_radius = 1.0 / f64::sqrt(24.0) / c.pixel_size;
+
+        assert_float_eq!(e.center_x, 50.5);
+        assert_float_eq!(e.center_y, 50.5);
+        assert_float_eq!(e.radius_x, expected_radius);
+        assert_float_eq!(e.radius_y, expected_radius);
+    }
+
+    #[test]
+    fn the_silhouette_matches_the_rays_that_graze_the_sphere() {
+        let c = camera_looking_at_the_origin();
+        let mut s = Sphere::new();
+        s.transform = Matrix4::scaling(0.5, 0.5, 0.5).translate(1.0, -0.5, 0.0);
+        let e = s.screen_silhouette(&c).unwrap();
+
+        assert!(e.radius_x > e.radius_y);
+        for i in 0..8 {
+            let angle = i as f64 * PI / 4.0;
+            let (sin, cos) = (angle.sin(), angle.cos());
+            let (rx, ry) = (e.radius_x * cos, e.radius_y * sin);
+            let x = e.center_x + rx * e.rotation.cos() - ry * e.rotation.sin();
+            let y = e.center_y + rx * e.rotation.sin() + ry * e.rotation.cos();
+            let inside = c.ray_for_subpixel(
+                0,
+                0,
+                x + (e.center_x - x) * 0.01,
+                y + (e.center_y - y) * 0.01,
+            );
+            let outside = c.ray_for_subpixel(
+                0,
+                0,
+                x - (e.center_x - x) * 0.01,
+                y - (e.center_y - y) * 0.01,
+            );
+
+            assert!(!s.intersect(inside).is_empty());
+            assert!(s.intersect(outside).is_empty());
+        }
+    }
+
+    #[test]
+    fn a_sphere_behind_the_camera_has_no_silhouette() {
+        let c = camera_looking_at_the_origin();
+        let mut s = Sphere::new();
+        s.transform = Matrix4::translation(0.0, 0.0, -10.0);
+
+        assert_eq!(s.screen_silhouette(&c), None);
+    }
+
+    #[test]
+    fn a_sphere_around_the_camera_has_no_silhouette() {
+        let c = camera_looking_at_the_origin();
+        let mut s = Sphere::new();
+        s.transform = Matrix4::scaling(10.0, 10.0, 10.0);
+
+        assert_eq!(s.screen_silhouette(&c), None);
+    }
+
+    #[test]
+    fn sampling_the_surface_of_a_unit_sphere() {
+        let s = Sphere::new();
+        for i in 0..5 {
+            for j in 0..5 {
+                let (u, v) = (i as f64 / 4.0, j as f64 / 4.0);
+                let (point, normal) = s.sample_surface(u, v).unwrap();
+                let from_center = point - Tuple::new_point(0.0, 0.0, 0.0);
+
+                assert!(point.is_point());
+                assert_float_eq!(from_center.magnitude(), 1.0);
+                assert_eq!(normal, from_center);
+            }
+        }
+    }
+
+    #[test]
+    fn sampling_the_surface_of_a_transformed_sphere() {
+        let mut s = Sphere::new();
+        s.transform = Matrix4::scaling(2.0, 2.0, 2.0).translate(0.0, 5.0, 0.0);
+        let (point, normal) = s.sample_surface(0.25, 0.5).unwrap();
+
+        assert_eq!(point, Tuple::new_point(0.0, 5.0, 2.0));
+        assert_eq!(normal, Tuple::new_vector(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn the_sampling_density_of_a_transformed_sphere() {
+        let mut s = Sphere::new();
+        assert_float_eq!(
+            s.sample_surface_pdf(Tuple::new_point(0.0, 1.0, 0.0))
+                .unwrap(),
+            1.0 / (4.0 * PI)
+        );
+
+        s.transform = Matrix4::scaling(2.0, 2.0, 2.0).translate(0.0, 5.0, 0.0);
+        assert_float_eq!(
+            s.sample_surface_pdf(Tuple::new_point(0.0, 5.0, 2.0))
+                .unwrap(),
+            1.0 / (16.0 * PI)
+        );
+
+        // Stretching along x spreads the samples out around the sides facing y and z, but
+        // not at the ends of the x axis.
+        s.transform = Matrix4::scaling(2.0, 1.0, 1.0);
+        assert_float_eq!(
+            s.sample_surface_pdf(Tuple::new_point(2.0, 0.0, 0.0))
+                .unwrap(),
+            1.0 / (4.0 * PI)
+        );
+        assert_float_eq!(
+            s.sample_surface_pdf(Tuple::new_point(0.0, 1.0, 0.0))
+                .unwrap(),
+            1.0 / (8.0 * PI)
+        );
+    }
+
+    #[test]
+    fn sampling_a_sphere_inside_groups_matches_giving_it_their_transform() {
+        let parent_transform = Matrix4::scaling(2.0, 1.0, 3.0).translate(0.0, 5.0, 0.0);
+        let grouped = Sphere::new();
+        let mut moved = Sphere::new();
+        moved.transform = parent_transform;
+        let examples = [(0.0, 0.0), (0.25, 0.5), (0.6, 0.3), (0.9, 0.8)];
+
+        for (u, v) in examples {
+            let (point, normal) = grouped
+                .sample_surface_within(parent_transform, u, v)
+                .unwrap();
+            assert_eq!((point, normal), moved.sample_surface(u, v).unwrap());
+            assert_float_eq!(
+                grouped
+                    .sample_surface_pdf_within(parent_transform, point)
+                    .unwrap(),
+                moved.sample_surface_pdf(point).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn the_closest_point_on_a_unit_sphere() {
+        let s = Sphere::new();
+
+        assert_eq!(
+            s.closest_point(Tuple::new_point(5.0, 0.0, 0.0)),
+            Some(Tuple::new_point(1.0, 0.0, 0.0))
+        );
+        assert_eq!(
+            s.closest_point(Tuple::new_point(0.0, 0.0, -0.5)),
+            Some(Tuple::new_point(0.0, 0.0, -1.0))
+        );
+    }
+
+    #[test]
+    fn the_closest_point_on_a_transformed_sphere() {
+        let mut s = Sphere::new();
+        s.transform = Matrix4::scaling(2.0, 2.0, 2.0).translate(0.0, 3.0, 0.0);
+
+        assert_eq!(
+            s.closest_point(Tuple::new_point(0.0, 3.0, 10.0)),
+            Some(Tuple::new_point(0.0, 3.0, 2.0))
+        );
+    }
+
+    #[test]
+    fn the_closest_point_on_a_sphere_inside_groups() {
+        let mut s = Sphere::new();
+        s.transform = Matrix4::scaling(2.0, 2.0, 2.0);
+        let parent_transform = Matrix4::translation(0.0, 3.0, 0.0);
+
+        assert_eq!(
+            s.closest_point_within(parent_transform, Tuple::new_point(0.0, 3.0, 10.0)),
+            Some(Tuple::new_point(0.0, 3.0, 2.0))
+        );
+    }
+
+    #[test]
+    fn the_closest_point_to_the_center_of_a_sphere_is_still_on_its_surface() {
+        let s = Sphere::new();
+        let p = s.closest_point(Tuple::new_point(0.0, 0.0, 0.0)).unwrap();
+
+        assert_float_eq!((p - Tuple::new_point(0.0, 0.0, 0.0)).magnitude(), 1.0);
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_the_union_of_two_spheres() {
+        let mut a = Sphere::new();
+        a.transform = Matrix4::translation(-0.5, 0.0, 0.0);
+        let mut b = Sphere::new();
+        b.transform = Matrix4::translation(0.5, 0.0, 0.0);
+        let r = Ray::new(
+            Tuple::new_point(-5.0, 0.0, 0.0),
+            Tuple::new_vector(1.0, 0.0, 0.0),
+        );
+        let xs = a.intersect_pair(&b, r);
+
+        assert_eq!(xs.len(), 2);
+        assert_float_eq!(xs[0].t, 3.5);
+        assert!(ptr::eq(xs[0].object, &a));
+        assert_float_eq!(xs[1].t, 6.5);
+        assert!(ptr::eq(xs[1].object, &b));
+    }
+
+    #[test]
+    fn a_csg_union_of_two_spheres_matches_intersect_pair() {
+        let mut a = Sphere::new();
+        a.transform = Matrix4::identity().translate(-0.6, 0.1, 0.0);
+        let mut b = Sphere::new();
+        b.transform = Matrix4::identity()
+            .scale(0.8, 0.8, 0.8)
+            .translate(0.5, -0.2, 0.3);
+        let c = Csg::new(Operation::Union, a.clone(), b.clone());
+        let mut rng = XorShift::new(461);
+        let mut coord = || rng.next_f64() * 4.0 - 2.0;
+
+        for _ in 0..1000 {
+            let origin = Tuple::new_point(coord() * 3.0, coord() * 3.0, -6.0);
+            let target = Tuple::new_point(coord(), coord(), coord());
+            let r = Ray::new(origin, (target - origin).normalize());
+            let expected = a.intersect_pair(&b, r);
+            let xs = c.intersect_children(r);
+
+            assert_eq!(xs.len(), expected.len());
+            for (x, e) in xs.iter().zip(expected.iter()) {
+                assert_float_eq!(x.t, e.t);
+                let side = if ptr::eq(e.object, &a) {
+                    &*c.left
+                } else {
+                    &*c.right
+                };
+                assert!(ptr::eq(x.object, side));
+            }
+        }
+    }
 }
@@ -0,0 +1,94 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Counters filled in while a world is being rendered. They are atomics so the world can be
+// shared by reference between render threads.
+#[derive(Debug, Default)]
+pub struct Stats {
+    primary_rays: AtomicU64,
+    shadow_rays: AtomicU64,
+    secondary_rays: AtomicU64,
+    intersection_tests: AtomicU64,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn primary_rays(&self) -> u64 {
+        self.primary_rays.load(Ordering::Relaxed)
+    }
+
+    pub fn shadow_rays(&self) -> u64 {
+        self.shadow_rays.load(Ordering::Relaxed)
+    }
+
+    pub fn secondary_rays(&self) -> u64 {
+        self.secondary_rays.load(Ordering::Relaxed)
+    }
+
+    pub fn intersection_tests(&self) -> u64 {
+        self.intersection_tests.load(Ordering::Relaxed)
+    }
+
+    pub fn record_primary_ray(&self) {
+        self.primary_rays.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_shadow_ray(&self) {
+        self.shadow_rays.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_secondary_ray(&self) {
+        self.secondary_rays.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_intersection_tests(&self, count: usize) {
+        self.intersection_tests
+            .fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        self.primary_rays.store(0, Ordering::Relaxed);
+        self.shadow_rays.store(0, Ordering::Relaxed);
+        self.secondary_rays.store(0, Ordering::Relaxed);
+        self.intersection_tests.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Clone for Stats {
+    fn clone(&self) -> Self {
+        Self {
+            primary_rays: AtomicU64::new(self.primary_rays()),
+            shadow_rays: AtomicU64::new(self.shadow_rays()),
+            secondary_rays: AtomicU64::new(self.secondary_rays()),
+            intersection_tests: AtomicU64::new(self.intersection_tests()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stats::Stats;
+
+    #[test]
+    fn recording_and_resetting_stats() {
+        let stats = Stats::new();
+        stats.record_primary_ray();
+        stats.record_primary_ray();
+        stats.record_shadow_ray();
+        stats.record_intersection_tests(3);
+
+        assert_eq!(stats.primary_rays(), 2);
+        assert_eq!(stats.shadow_rays(), 1);
+        assert_eq!(stats.secondary_rays(), 0);
+        assert_eq!(stats.intersection_tests(), 3);
+
+        let copy = stats.clone();
+        stats.reset();
+
+        assert_eq!(stats.primary_rays(), 0);
+        assert_eq!(stats.intersection_tests(), 0);
+        assert_eq!(copy.primary_rays(), 2);
+    }
+}
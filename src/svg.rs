@@ -0,0 +1,100 @@
+use crate::camera::Camera;
+use crate::tuple::Tuple;
+use crate::world::{World, WorldShape};
+use std::fmt::Write;
+
+// Draws every sphere in the world as a flat ellipse in its base color, with no lighting. Other
+// shapes are skipped. Spheres are painted back to front so nearer ones cover farther ones.
+pub fn to_svg(camera: &Camera, world: &World) -> String {
+    let mut ellipses = world
+        .shapes()
+        .filter_map(|shape| match shape {
+            WorldShape::Sphere(sphere) => {
+                let ellipse = sphere.screen_silhouette(camera)?;
+                let center = camera.transform * sphere.transform * Tuple::new_point(0.0, 0.0, 0.0);
+                Some((center.z, ellipse, sphere.material.color))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    ellipses.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut result = String::new();
+    writeln!(
+        &mut result,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+        camera.hsize, camera.vsize
+    )
+    .unwrap();
+    for (_, e, color) in ellipses {
+        writeln!(
+            &mut result,
+            r#"  <ellipse cx="{:.3}" cy="{:.3}" rx="{:.3}" ry="{:.3}" transform="rotate({:.3} {:.3} {:.3})" fill="{}"/>"#,
+            e.center_x,
+            e.center_y,
+            e.radius_x,
+            e.radius_y,
+            e.rotation.to_degrees(),
+            e.center_x,
+            e.center_y,
+            color.to_hex()
+        )
+        .unwrap();
+    }
+    result.push_str("</svg>\n");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::camera::Camera;
+    use crate::color::Color;
+    use crate::matrix::Matrix4;
+    use crate::plane::Plane;
+    use crate::sphere::Sphere;
+    use crate::svg::to_svg;
+    use crate::tuple::Tuple;
+    use crate::world::World;
+    use std::f64::consts::PI;
+
+    fn camera() -> Camera {
+        let mut c = Camera::new(101, 101, PI / 2.0);
+        c.transform = Matrix4::view_transform(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        c
+    }
+
+    #[test]
+    fn a_one_sphere_scene_produces_one_ellipse() {
+        let mut s = Sphere::new();
+        s.material.color = Color::new(1.0, 0.0, 0.5);
+        let mut w: World = World::new();
+        w.objects.push(s.into());
+        w.objects.push(Plane::new().into());
+        let svg = to_svg(&camera(), &w);
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<ellipse").count(), 1);
+        assert!(svg.contains(r#"cx="50.500" cy="50.500""#));
+        assert!(svg.contains(r##"fill="#ff0080""##));
+    }
+
+    #[test]
+    fn farther_spheres_are_painted_first() {
+        let mut near = Sphere::new();
+        near.material.color = Color::new(1.0, 0.0, 0.0);
+        let mut far = Sphere::new();
+        far.transform = Matrix4::translation(0.0, 0.0, 5.0);
+        far.material.color = Color::new(0.0, 0.0, 1.0);
+        let mut w: World = World::new();
+        w.objects.push(near.into());
+        w.objects.push(far.into());
+        let svg = to_svg(&camera(), &w);
+
+        assert!(svg.find("#0000ff").unwrap() < svg.find("#ff0000").unwrap());
+    }
+}
@@ -0,0 +1,186 @@
+use crate::bounds::Bounds;
+use crate::color::Color;
+use crate::intersections::Intersections;
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::pattern::Pattern;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::sphere::Sphere;
+use crate::tuple::Tuple;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+// A shape that never reports intersections but remembers every object-space ray it was given,
+// which makes it easy to check what the intersect pipeline hands to local_intersect. It also
+// counts how often its bounds are asked for.
+#[derive(Debug)]
+pub struct RecordingShape {
+    pub transform: Matrix4,
+    pub material: Material,
+    rays: Mutex<Vec<Ray>>,
+    pub name: Option<String>,
+    bounds_queries: AtomicUsize,
+}
+
+impl RecordingShape {
+    pub fn new() -> Self {
+        Self {
+            transform: Matrix4::identity(),
+            material: Material::new(),
+            rays: Mutex::new(Vec::new()),
+            name: None,
+            bounds_queries: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn rays(&self) -> Vec<Ray> {
+        self.rays.lock().unwrap().clone()
+    }
+
+    pub fn bounds_queries(&self) -> usize {
+        self.bounds_queries.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for RecordingShape {
+    fn default() -> Self {
+        RecordingShape::new()
+    }
+}
+
+impl Clone for RecordingShape {
+    fn clone(&self) -> Self {
+        Self {
+            transform: self.transform,
+            material: self.material.clone(),
+            rays: Mutex::new(self.rays()),
+            name: self.name.clone(),
+            bounds_queries: AtomicUsize::new(self.bounds_queries()),
+        }
+    }
+}
+
+impl Shape for RecordingShape {
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Matrix4 {
+        &mut self.transform
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn set_name(&mut self, name: impl Into<String>) {
+        self.name = Some(name.into());
+    }
+
+    fn local_intersect(&self, local_ray: Ray) -> Intersections<'_, Self> {
+        self.rays.lock().unwrap().push(local_ray);
+        Intersections::new_unsorted(Vec::new())
+    }
+
+    fn local_normal_at(&self, local_point: Tuple) -> Tuple {
+        Tuple::new_vector(local_point.x, local_point.y, local_point.z)
+    }
+
+    fn local_normals_are_unit(&self) -> bool {
+        false
+    }
+
+    // As big as a unit sphere, so culling by bounds can be observed through the recorded rays.
+    fn local_bounds(&self) -> Bounds {
+        self.bounds_queries.fetch_add(1, Ordering::Relaxed);
+        Bounds::new(
+            Tuple::new_point(-1.0, -1.0, -1.0),
+            Tuple::new_point(1.0, 1.0, 1.0),
+        )
+    }
+
+    fn max_intersections(&self) -> usize {
+        0
+    }
+}
+
+// Returns the pattern-space point as a color, which shows exactly where a pattern was sampled.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TestPattern {
+    pub transform: Matrix4,
+}
+
+impl TestPattern {
+    pub fn new() -> Self {
+        Self {
+            transform: Matrix4::identity(),
+        }
+    }
+}
+
+impl Default for TestPattern {
+    fn default() -> Self {
+        TestPattern::new()
+    }
+}
+
+impl Pattern for TestPattern {
+    fn transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Matrix4 {
+        &mut self.transform
+    }
+
+    fn pattern_at(&self, point: Tuple) -> Color {
+        Color::new(point.x, point.y, point.z)
+    }
+}
+
+pub fn glass_sphere() -> Sphere {
+    let mut s = Sphere::new();
+    s.material.transparency = 1.0;
+    s.material.refractive_index = 1.5;
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::Matrix4;
+    use crate::ray::Ray;
+    use crate::shape::Shape;
+    use crate::testing::RecordingShape;
+    use crate::tuple::Tuple;
+
+    #[test]
+    fn a_recording_shape_records_every_ray_in_order() {
+        let mut s = RecordingShape::new();
+        s.transform = Matrix4::translation(5.0, 0.0, 0.0);
+        let r1 = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let r2 = Ray::new(
+            Tuple::new_point(1.0, 2.0, 3.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let _ = s.intersect(r1);
+        let _ = s.intersect(r2);
+
+        let rays = s.rays();
+        assert_eq!(rays.len(), 2);
+        assert_eq!(rays[0].origin, Tuple::new_point(-5.0, 0.0, -5.0));
+        assert_eq!(rays[1].origin, Tuple::new_point(-4.0, 2.0, 3.0));
+        assert_eq!(rays[1].direction, r2.direction);
+    }
+}
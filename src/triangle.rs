@@ -0,0 +1,173 @@
+use crate::bounds::Bounds;
+use crate::intersections::{Intersection, Intersections};
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::tuple::Tuple;
+use crate::EPSILON;
+
+// A flat triangle. The edges and normal are worked out once, when it is created.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triangle {
+    pub p1: Tuple,
+    pub p2: Tuple,
+    pub p3: Tuple,
+    pub e1: Tuple,
+    pub e2: Tuple,
+    pub normal: Tuple,
+    pub transform: Matrix4,
+    pub material: Material,
+    pub name: Option<String>,
+}
+
+impl Triangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Self {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(e1).normalize(),
+            transform: Matrix4::identity(),
+            material: Material::new(),
+            name: None,
+        }
+    }
+}
+
+impl Shape for Triangle {
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Matrix4 {
+        &mut self.transform
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn set_name(&mut self, name: impl Into<String>) {
+        self.name = Some(name.into());
+    }
+
+    // Möller–Trumbore: solves for the barycentric coordinates of the hit and checks that they
+    // are inside the triangle.
+    fn local_intersect(&self, local_ray: Ray) -> Intersections<'_, Self> {
+        let dir_cross_e2 = local_ray.direction.cross(self.e2);
+        let det = self.e1 * dir_cross_e2;
+        if det.abs() < EPSILON {
+            return Intersections::new_unsorted(Vec::new());
+        }
+        let f = 1.0 / det;
+        let p1_to_origin = local_ray.origin - self.p1;
+        let u = f * (p1_to_origin * dir_cross_e2);
+        if !(0.0..=1.0).contains(&u) {
+            return Intersections::new_unsorted(Vec::new());
+        }
+        let origin_cross_e1 = p1_to_origin.cross(self.e1);
+        let v = f * (local_ray.direction * origin_cross_e1);
+        if v < 0.0 || u + v > 1.0 {
+            return Intersections::new_unsorted(Vec::new());
+        }
+        let t = f * (self.e2 * origin_cross_e1);
+        Intersections::new_unsorted(vec![Intersection::new(t, self)])
+    }
+
+    fn local_normal_at(&self, _local_point: Tuple) -> Tuple {
+        self.normal
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        let mut bounds = Bounds::empty();
+        bounds.add_point(self.p1);
+        bounds.add_point(self.p2);
+        bounds.add_point(self.p3);
+        bounds
+    }
+
+    fn max_intersections(&self) -> usize {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ray::Ray;
+    use crate::shape::Shape;
+    use crate::triangle::Triangle;
+    use crate::tuple::Tuple;
+
+    fn test_triangle() -> Triangle {
+        Triangle::new(
+            Tuple::new_point(0.0, 1.0, 0.0),
+            Tuple::new_point(-1.0, 0.0, 0.0),
+            Tuple::new_point(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn constructing_a_triangle() {
+        let t = test_triangle();
+
+        assert_eq!(t.e1, Tuple::new_vector(-1.0, -1.0, 0.0));
+        assert_eq!(t.e2, Tuple::new_vector(1.0, -1.0, 0.0));
+        assert_eq!(t.normal, Tuple::new_vector(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn finding_the_normal_on_a_triangle() {
+        let t = test_triangle();
+        let points = [
+            Tuple::new_point(0.0, 0.5, 0.0),
+            Tuple::new_point(-0.5, 0.75, 0.0),
+            Tuple::new_point(0.5, 0.25, 0.0),
+        ];
+
+        for point in points {
+            assert_eq!(t.local_normal_at(point), t.normal);
+        }
+    }
+
+    #[test]
+    fn a_ray_misses_a_triangle() {
+        let t = test_triangle();
+        let examples = [
+            ((0.0, -1.0, -2.0), (0.0, 1.0, 0.0)),
+            ((1.0, 1.0, -2.0), (0.0, 0.0, 1.0)),
+            ((-1.0, 1.0, -2.0), (0.0, 0.0, 1.0)),
+            ((0.0, -1.0, -2.0), (0.0, 0.0, 1.0)),
+        ];
+
+        for ((ox, oy, oz), (dx, dy, dz)) in examples {
+            let r = Ray::new(Tuple::new_point(ox, oy, oz), Tuple::new_vector(dx, dy, dz));
+            assert!(t.local_intersect(r).is_empty());
+        }
+    }
+
+    #[test]
+    fn a_ray_strikes_a_triangle() {
+        let t = test_triangle();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.5, -2.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = t.local_intersect(r);
+
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 2.0);
+    }
+}
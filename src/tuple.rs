@@ -30,6 +30,10 @@ impl Tuple {
         float_eq(self.w, 0.0)
     }
 
+    pub fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite() && self.z.is_finite() && self.w.is_finite()
+    }
+
     pub fn magnitude(&self) -> f64 {
         f64::sqrt(self.x.powi(2) + self.y.powi(2) + self.z.powi(2) + self.w.powi(2))
     }
@@ -55,6 +59,14 @@ impl Tuple {
     pub fn reflect(&self, normal: Tuple) -> Self {
         *self - normal * 2.0 * (*self * normal)
     }
+
+    pub fn lerp(&self, other: Self, t: f64) -> Self {
+        *self + (other - *self) * t
+    }
+
+    pub fn distance(&self, other: Self) -> f64 {
+        (other - *self).magnitude()
+    }
 }
 
 impl PartialEq for Tuple {
@@ -411,4 +423,23 @@ mod tests {
 
         assert_eq!(r, expected);
     }
+
+    #[test]
+    fn lerping_between_two_points() {
+        let a = Tuple::new_point(1.0, 2.0, 3.0);
+        let b = Tuple::new_point(3.0, -2.0, 5.0);
+
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 0.5), Tuple::new_point(2.0, 0.0, 4.0));
+    }
+
+    #[test]
+    fn the_distance_between_two_points() {
+        let a = Tuple::new_point(0.0, 0.0, 0.0);
+        let b = Tuple::new_point(3.0, 4.0, 0.0);
+
+        assert_float_eq!(a.distance(b), 5.0);
+        assert_float_eq!(b.distance(a), 5.0);
+    }
 }
@@ -1,18 +1,37 @@
+use crate::bounds::Bounds;
 use crate::color::Color;
-use crate::intersections::{Computations, Intersection, Intersections};
+use crate::cone::Cone;
+use crate::csg::Csg;
+use crate::cube::Cube;
+use crate::group::Group;
+use crate::intersections::{intersect_all, Computations, Intersection, Intersections};
 use crate::light::PointLight;
 use crate::material::Material;
 use crate::matrix::Matrix4;
 use crate::plane::Plane;
+use crate::random::XorShift;
 use crate::ray::Ray;
 use crate::shape::Shape;
 use crate::sphere::Sphere;
+use crate::stats::Stats;
+use crate::triangle::Triangle;
 use crate::tuple::Tuple;
+use crate::EPSILON;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::mem;
+use std::ptr;
+use std::sync::Mutex;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum WorldShape {
     Sphere(Sphere),
     Plane(Plane),
+    Cube(Cube),
+    Cone(Cone),
+    Group(Group),
+    Csg(Csg),
+    Triangle(Triangle),
 }
 
 impl From<Sphere> for WorldShape {
@@ -27,11 +46,46 @@ impl From<Plane> for WorldShape {
     }
 }
 
+impl From<Cube> for WorldShape {
+    fn from(cube: Cube) -> Self {
+        Self::Cube(cube)
+    }
+}
+
+impl From<Cone> for WorldShape {
+    fn from(cone: Cone) -> Self {
+        Self::Cone(cone)
+    }
+}
+
+impl From<Group> for WorldShape {
+    fn from(group: Group) -> Self {
+        Self::Group(group)
+    }
+}
+
+impl From<Csg> for WorldShape {
+    fn from(csg: Csg) -> Self {
+        Self::Csg(csg)
+    }
+}
+
+impl From<Triangle> for WorldShape {
+    fn from(triangle: Triangle) -> Self {
+        Self::Triangle(triangle)
+    }
+}
+
 impl Shape for WorldShape {
     fn material(&self) -> &Material {
         match self {
             WorldShape::Sphere(sphere) => sphere.material(),
             WorldShape::Plane(plane) => plane.material(),
+            WorldShape::Cube(cube) => cube.material(),
+            WorldShape::Cone(cone) => cone.material(),
+            WorldShape::Group(group) => &group.material,
+            WorldShape::Csg(csg) => &csg.material,
+            WorldShape::Triangle(triangle) => triangle.material(),
         }
     }
 
@@ -39,6 +93,11 @@ impl Shape for WorldShape {
         match self {
             WorldShape::Sphere(sphere) => sphere.material_mut(),
             WorldShape::Plane(plane) => plane.material_mut(),
+            WorldShape::Cube(cube) => cube.material_mut(),
+            WorldShape::Cone(cone) => cone.material_mut(),
+            WorldShape::Group(group) => &mut group.material,
+            WorldShape::Csg(csg) => &mut csg.material,
+            WorldShape::Triangle(triangle) => triangle.material_mut(),
         }
     }
 
@@ -46,6 +105,11 @@ impl Shape for WorldShape {
         match self {
             WorldShape::Sphere(sphere) => sphere.transform(),
             WorldShape::Plane(plane) => plane.transform(),
+            WorldShape::Cube(cube) => cube.transform(),
+            WorldShape::Cone(cone) => cone.transform(),
+            WorldShape::Group(group) => &group.transform,
+            WorldShape::Csg(csg) => &csg.transform,
+            WorldShape::Triangle(triangle) => triangle.transform(),
         }
     }
 
@@ -53,285 +117,1937 @@ impl Shape for WorldShape {
         match self {
             WorldShape::Sphere(sphere) => sphere.transform_mut(),
             WorldShape::Plane(plane) => plane.transform_mut(),
+            WorldShape::Cube(cube) => cube.transform_mut(),
+            WorldShape::Cone(cone) => cone.transform_mut(),
+            WorldShape::Group(group) => &mut group.transform,
+            WorldShape::Csg(csg) => &mut csg.transform,
+            WorldShape::Triangle(triangle) => triangle.transform_mut(),
         }
     }
 
-    fn local_intersect(&self, local_ray: Ray) -> Intersections<Self> {
-        Intersections::new(
-            match self {
-                WorldShape::Sphere(sphere) => sphere
-                    .local_intersect(local_ray)
-                    .iter()
-                    .map(|x| x.t)
-                    .collect::<Vec<_>>(),
-                WorldShape::Plane(plane) => plane
-                    .local_intersect(local_ray)
-                    .iter()
-                    .map(|x| x.t)
-                    .collect::<Vec<_>>(),
+    fn local_intersect(&self, local_ray: Ray) -> Intersections<'_, Self> {
+        match self {
+            WorldShape::Sphere(sphere) => as_world_shape(sphere.local_intersect(local_ray), self),
+            WorldShape::Plane(plane) => as_world_shape(plane.local_intersect(local_ray), self),
+            WorldShape::Cube(cube) => as_world_shape(cube.local_intersect(local_ray), self),
+            WorldShape::Cone(cone) => as_world_shape(cone.local_intersect(local_ray), self),
+            // The shapes inside a group or CSG shape are the objects that get hit, not the
+            // container.
+            WorldShape::Group(group) => group.intersect_children(local_ray),
+            WorldShape::Csg(csg) => csg.intersect_children(local_ray),
+            WorldShape::Triangle(triangle) => {
+                as_world_shape(triangle.local_intersect(local_ray), self)
             }
-            .into_iter()
-            .map(|x| Intersection::<Self>::new(x, self))
-            .collect::<Vec<_>>(),
-        )
+        }
     }
 
     fn local_normal_at(&self, local_point: Tuple) -> Tuple {
         match self {
             WorldShape::Sphere(sphere) => sphere.local_normal_at(local_point),
             WorldShape::Plane(plane) => plane.local_normal_at(local_point),
+            WorldShape::Cube(cube) => cube.local_normal_at(local_point),
+            WorldShape::Cone(cone) => cone.local_normal_at(local_point),
+            // Hits never land on a container, so nothing asks for its normal.
+            WorldShape::Group(_) | WorldShape::Csg(_) => {
+                unreachable!("groups and CSG shapes have no surface of their own")
+            }
+            WorldShape::Triangle(triangle) => triangle.local_normal_at(local_point),
         }
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct World<S: Shape = WorldShape> {
-    pub objects: Vec<S>,
-    pub light: Option<PointLight>,
-}
+    fn local_normals_are_unit(&self) -> bool {
+        match self {
+            WorldShape::Cone(cone) => cone.local_normals_are_unit(),
+            _ => true,
+        }
+    }
 
-impl<S: Shape> World<S> {
-    pub fn new() -> Self {
-        Self {
-            objects: Vec::new(),
-            light: None,
+    fn clip(&self) -> Option<(Tuple, Tuple)> {
+        match self {
+            WorldShape::Sphere(sphere) => sphere.clip(),
+            WorldShape::Plane(plane) => plane.clip(),
+            WorldShape::Cube(cube) => cube.clip(),
+            WorldShape::Cone(cone) => cone.clip(),
+            WorldShape::Group(_) | WorldShape::Csg(_) => None,
+            WorldShape::Triangle(triangle) => triangle.clip(),
         }
     }
 
-    pub fn intersect_world(&self, r: Ray) -> Intersections<S> {
-        let mut xs = Vec::new();
-        for object in self.objects.iter() {
-            xs.extend_from_slice(object.intersect(r).as_ref());
+    fn name(&self) -> Option<&str> {
+        match self {
+            WorldShape::Sphere(sphere) => sphere.name(),
+            WorldShape::Plane(plane) => plane.name(),
+            WorldShape::Cube(cube) => cube.name(),
+            WorldShape::Cone(cone) => cone.name(),
+            WorldShape::Group(group) => group.name.as_deref(),
+            WorldShape::Csg(csg) => csg.name.as_deref(),
+            WorldShape::Triangle(triangle) => triangle.name(),
         }
-        xs.sort_by(|lhs, rhs| lhs.t.partial_cmp(&rhs.t).unwrap());
-        Intersections::new(xs)
     }
 
-    pub fn shade_hit(&self, comps: Computations<S>) -> Color {
-        let shadowed = self.is_shadowed(comps.over_point);
-        comps.object.material().lighting(
-            self.light.unwrap(),
-            comps.point,
-            comps.eyev,
-            comps.normalv,
-            shadowed,
-        )
+    fn set_name(&mut self, name: impl Into<String>) {
+        match self {
+            WorldShape::Sphere(sphere) => sphere.set_name(name),
+            WorldShape::Plane(plane) => plane.set_name(name),
+            WorldShape::Cube(cube) => cube.set_name(name),
+            WorldShape::Cone(cone) => cone.set_name(name),
+            WorldShape::Group(group) => group.name = Some(name.into()),
+            WorldShape::Csg(csg) => csg.name = Some(name.into()),
+            WorldShape::Triangle(triangle) => triangle.set_name(name),
+        }
     }
 
-    pub fn color_at(&self, r: Ray) -> Color {
-        let xs = self.intersect_world(r);
-        if let Some(hit) = xs.hit() {
-            let comps = hit.prepare_computations(r);
-            self.shade_hit(comps)
-        } else {
-            Color::new(0.0, 0.0, 0.0)
+    fn local_closest_point(&self, local_point: Tuple) -> Option<Tuple> {
+        match self {
+            WorldShape::Sphere(sphere) => sphere.local_closest_point(local_point),
+            WorldShape::Plane(plane) => plane.local_closest_point(local_point),
+            WorldShape::Cube(cube) => cube.local_closest_point(local_point),
+            WorldShape::Cone(cone) => cone.local_closest_point(local_point),
+            WorldShape::Group(_) | WorldShape::Csg(_) => None,
+            WorldShape::Triangle(triangle) => triangle.local_closest_point(local_point),
         }
     }
 
-    pub fn is_shadowed(&self, point: Tuple) -> bool {
-        let v = self.light.unwrap().position - point;
-        let distance = v.magnitude();
-        let direction = v.normalize();
+    fn local_sample_surface(&self, u: f64, v: f64) -> Option<Tuple> {
+        match self {
+            WorldShape::Sphere(sphere) => sphere.local_sample_surface(u, v),
+            WorldShape::Plane(plane) => plane.local_sample_surface(u, v),
+            WorldShape::Cube(cube) => cube.local_sample_surface(u, v),
+            WorldShape::Cone(cone) => cone.local_sample_surface(u, v),
+            WorldShape::Group(_) | WorldShape::Csg(_) => None,
+            WorldShape::Triangle(triangle) => triangle.local_sample_surface(u, v),
+        }
+    }
 
-        let r = Ray::new(point, direction);
-        let intersections = self.intersect_world(r);
+    fn local_surface_area(&self) -> Option<f64> {
+        match self {
+            WorldShape::Sphere(sphere) => sphere.local_surface_area(),
+            WorldShape::Plane(plane) => plane.local_surface_area(),
+            WorldShape::Cube(cube) => cube.local_surface_area(),
+            WorldShape::Cone(cone) => cone.local_surface_area(),
+            WorldShape::Group(_) | WorldShape::Csg(_) => None,
+            WorldShape::Triangle(triangle) => triangle.local_surface_area(),
+        }
+    }
 
-        let h = intersections.hit();
-        h.map_or(false, |h| h.t < distance)
+    fn local_bounds(&self) -> Bounds {
+        match self {
+            WorldShape::Sphere(sphere) => sphere.local_bounds(),
+            WorldShape::Plane(plane) => plane.local_bounds(),
+            WorldShape::Cube(cube) => cube.local_bounds(),
+            WorldShape::Cone(cone) => cone.local_bounds(),
+            WorldShape::Group(group) => group.local_bounds(),
+            WorldShape::Csg(csg) => csg.local_bounds(),
+            WorldShape::Triangle(triangle) => triangle.local_bounds(),
+        }
     }
-}
 
-impl<S: Shape> Default for World<S> {
-    fn default() -> Self {
-        World::new()
+    fn includes(&self, object: &Self) -> bool {
+        match self {
+            WorldShape::Group(group) => group.children().iter().any(|c| c.includes(object)),
+            WorldShape::Csg(csg) => csg.left.includes(object) || csg.right.includes(object),
+            _ => ptr::eq(self, object),
+        }
     }
-}
 
-#[doc(hidden)]
-pub fn default_world() -> World<Sphere> {
-    let light = PointLight::new(
-        Tuple::new_point(-10.0, 10.0, -10.0),
-        Color::new(1.0, 1.0, 1.0),
-    );
-    let mut s1 = Sphere::new();
-    s1.material.color = Color::new(0.8, 1.0, 0.6);
-    s1.material.diffuse = 0.7;
-    s1.material.specular = 0.2;
-    let mut s2 = Sphere::new();
-    s2.transform = Matrix4::scaling(0.5, 0.5, 0.5);
+    // Parts of the surfaces inside a CSG shape are cut away, so sampling them would light points
+    // from surface that isn't there. Their emission is left to the rays that hit them.
+    fn visit_surfaces<'a>(
+        &'a self,
+        parent_transform: Matrix4,
+        f: &mut dyn FnMut(&'a Self, Matrix4),
+    ) {
+        match self {
+            WorldShape::Group(group) => {
+                for child in group.children() {
+                    child.visit_surfaces(parent_transform * group.transform, f);
+                }
+            }
+            WorldShape::Csg(_) => {}
+            _ => f(self, parent_transform),
+        }
+    }
 
-    World {
-        objects: vec![s1, s2],
-        light: Some(light),
+    fn max_intersections(&self) -> usize {
+        match self {
+            WorldShape::Sphere(sphere) => sphere.max_intersections(),
+            WorldShape::Plane(plane) => plane.max_intersections(),
+            WorldShape::Cube(cube) => cube.max_intersections(),
+            WorldShape::Cone(cone) => cone.max_intersections(),
+            WorldShape::Group(group) => group.max_intersections(),
+            WorldShape::Csg(csg) => csg.max_intersections(),
+            WorldShape::Triangle(triangle) => triangle.max_intersections(),
+        }
+    }
+
+    fn casts_shadow(&self) -> bool {
+        match self {
+            WorldShape::Group(group) => group.casts_shadow(),
+            WorldShape::Csg(csg) => csg.left.casts_shadow() || csg.right.casts_shadow(),
+            _ => self.material().cast_shadow,
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::color::Color;
-    use crate::intersections::Intersection;
-    use crate::light::PointLight;
-    use crate::matrix::Matrix4;
-    use crate::ray::Ray;
-    use crate::sphere::Sphere;
-    use crate::tuple::Tuple;
-    use crate::world::{default_world, World};
-    use crate::{assert_float_eq, EPSILON};
+// How many times rays may bounce between reflective surfaces.
+const RECURSION_DEPTH: usize = 5;
+// Less than this can't change an 8 bit channel.
+const MIN_THROUGHPUT: f64 = 1.0 / 255.0;
+const ROULETTE_MAX_SURVIVAL: f64 = 0.95;
+const CONTACT_SOFTNESS_SAMPLES: usize = 16;
+const GLOSSY_SAMPLES: usize = 8;
+// Steps per unit of each direction component, so buckets are about a thousandth of a radian wide.
+const BACKGROUND_CACHE_BUCKETS: f64 = 1024.0;
+// PI * (3 - sqrt(5)), which spreads successive samples as evenly as possible.
+const GOLDEN_ANGLE: f64 = 2.399_963_229_728_653;
 
-    #[test]
-    fn creating_a_world() {
-        let w: World = World::new();
+// A direction in the hemisphere around the normal, more likely the closer it is to the normal,
+// in proportion to the cosine of the angle between them.
+fn cosine_weighted_direction(normal: Tuple, rng: &mut XorShift) -> Tuple {
+    let (tangent, bitangent) = tangent_basis(normal);
+    let phi = 2.0 * PI * rng.next_f64();
+    let r2 = rng.next_f64();
+    let radius = r2.sqrt();
+    tangent * (radius * phi.cos()) + bitangent * (radius * phi.sin()) + normal * (1.0 - r2).sqrt()
+}
 
-        assert!(w.objects.is_empty());
-        assert_eq!(w.light, None)
-    }
+// The hits on the shape an enum value holds, with the enum value as their object.
+fn as_world_shape<'a, T: Shape>(
+    xs: Intersections<'_, T>,
+    object: &'a WorldShape,
+) -> Intersections<'a, WorldShape> {
+    Intersections::new_unsorted(xs.iter().map(|x| Intersection::new(x.t, object)).collect())
+}
 
-    #[test]
-    fn the_default_world() {
-        let light = PointLight::new(
-            Tuple::new_point(-10.0, 10.0, -10.0),
-            Color::new(1.0, 1.0, 1.0),
-        );
-        let mut s1 = Sphere::new();
-        s1.material.color = Color::new(0.8, 1.0, 0.6);
-        s1.material.diffuse = 0.7;
-        s1.material.specular = 0.2;
-        let mut s2 = Sphere::new();
-        s2.transform = Matrix4::scaling(0.5, 0.5, 0.5);
-        let w = default_world();
+// Two unit vectors perpendicular to the normal and to each other.
+fn tangent_basis(normal: Tuple) -> (Tuple, Tuple) {
+    let helper = if normal.x.abs() > 0.9 {
+        Tuple::new_vector(0.0, 1.0, 0.0)
+    } else {
+        Tuple::new_vector(1.0, 0.0, 0.0)
+    };
+    let tangent = helper.cross(normal).normalize();
+    (tangent, normal.cross(tangent))
+}
 
-        assert_eq!(w.light, Some(light));
-        assert!(w.objects.contains(&s1));
-        assert!(w.objects.contains(&s2));
+// The false color for a ray that reflected off the given number of mirrors, going around the
+// color wheel.
+fn depth_tint(bounces: usize) -> Color {
+    match bounces % 6 {
+        0 => Color::new(1.0, 0.0, 0.0),
+        1 => Color::new(1.0, 1.0, 0.0),
+        2 => Color::new(0.0, 1.0, 0.0),
+        3 => Color::new(0.0, 1.0, 1.0),
+        4 => Color::new(0.0, 0.0, 1.0),
+        _ => Color::new(1.0, 0.0, 1.0),
     }
+}
 
-    #[test]
-    fn intersect_a_world_with_a_ray() {
-        let w = default_world();
-        let r = Ray::new(
-            Tuple::new_point(0.0, 0.0, -5.0),
-            Tuple::new_vector(0.0, 0.0, 1.0),
-        );
-        let xs = w.intersect_world(r);
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AmbientOcclusion {
+    pub samples: usize,
+    pub radius: f64,
+}
 
-        assert_eq!(xs.len(), 4);
-        assert_float_eq!(xs[0].t, 4.0);
-        assert_float_eq!(xs[1].t, 4.5);
-        assert_float_eq!(xs[2].t, 5.5);
-        assert_float_eq!(xs[3].t, 6.0);
+impl AmbientOcclusion {
+    pub fn new(samples: usize, radius: f64) -> Self {
+        Self { samples, radius }
     }
+}
 
-    #[test]
-    fn shading_an_intersection() {
-        let w = default_world();
-        let r = Ray::new(
-            Tuple::new_point(0.0, 0.0, -5.0),
-            Tuple::new_vector(0.0, 0.0, 1.0),
-        );
-        let shape = w.objects[0];
-        let i = Intersection::new(4.0, &shape);
-        let comps = i.prepare_computations(r);
-        let c = w.shade_hit(comps);
+// When a path traced ray stops bouncing. Russian roulette randomly ends paths once they have made
+// min_bounces bounces, more often the less their throughput, and scales up the ones that go on,
+// so the average stays the same as with unlimited bounces.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PathTermination {
+    MaxBounces(usize),
+    RussianRoulette { min_bounces: usize },
+}
 
-        assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
-    }
+// An emissive shape that next event estimation samples directly, with the combined transforms of
+// the groups it is in.
+#[derive(Debug)]
+pub struct Emitter<'a, S: Shape> {
+    pub object: &'a S,
+    pub parent_transform: Matrix4,
+}
 
-    #[test]
-    fn shading_an_intersection_from_the_inside() {
-        let mut w = default_world();
-        w.light = Some(PointLight::new(
-            Tuple::new_point(0.0, 0.25, 0.0),
-            Color::new(1.0, 1.0, 1.0),
-        ));
-        let r = Ray::new(
-            Tuple::new_point(0.0, 0.0, 0.0),
-            Tuple::new_vector(0.0, 0.0, 1.0),
-        );
-        let shape = w.objects[1];
-        let i = Intersection::new(0.5, &shape);
-        let comps = i.prepare_computations(r);
-        let c = w.shade_hit(comps);
+// A homogeneous participating medium, ray marched in the given number of steps.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Fog {
+    pub density: f64,
+    pub steps: usize,
+}
 
-        assert_eq!(c, Color::new(0.90498, 0.90498, 0.90498));
+impl Fog {
+    pub fn new(density: f64, steps: usize) -> Self {
+        Self { density, steps }
     }
+}
 
-    #[test]
-    fn the_color_when_a_ray_misses() {
-        let w = default_world();
-        let r = Ray::new(
-            Tuple::new_point(0.0, 0.0, -5.0),
-            Tuple::new_vector(0.0, 1.0, 0.0),
-        );
-        let c = w.color_at(r);
+// What a ray that misses every object sees. The gradient blends from bottom, looking straight
+// down, to top, looking straight up.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Background {
+    Solid(Color),
+    Gradient { bottom: Color, top: Color },
+}
 
-        assert_eq!(c, Color::new(0.0, 0.0, 0.0));
+impl Background {
+    pub fn color_at(&self, direction: Tuple) -> Color {
+        match *self {
+            Background::Solid(color) => color,
+            Background::Gradient { bottom, top } => {
+                let t = 0.5 * (direction.normalize().y + 1.0);
+                bottom * (1.0 - t) + top * t
+            }
+        }
     }
+}
 
-    #[test]
-    fn the_color_when_a_ray_hits() {
-        let w = default_world();
-        let r = Ray::new(
-            Tuple::new_point(0.0, 0.0, -5.0),
-            Tuple::new_vector(0.0, 0.0, 1.0),
-        );
-        let c = w.color_at(r);
-
-        assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
+impl Default for Background {
+    fn default() -> Self {
+        Background::Solid(Color::new(0.0, 0.0, 0.0))
     }
+}
 
-    #[test]
-    fn the_color_with_an_intersection_behind_the_ray() {
-        let mut w = default_world();
-        let inner = {
-            let mut outer = &mut w.objects[0];
-            outer.material.ambient = 1.0;
-            let inner = &mut w.objects[1];
-            inner.material.ambient = 1.0;
-            *inner
-        };
-        let r = Ray::new(
-            Tuple::new_point(0.0, 0.0, 0.75),
-            Tuple::new_vector(0.0, 0.0, -1.0),
-        );
-        let c = w.color_at(r);
+// Remembers background colors by ray direction, for when many rays miss in the same direction, as
+// with an orthographic camera. Directions are bucketed by rounding, but a bucket only answers for
+// the exact direction it was filled with, so the image is the same with or without the cache. Once
+// it is full, the entry used longest ago makes way. A mutex guards it so render threads can share
+// the world.
+#[derive(Debug)]
+pub struct BackgroundCache {
+    capacity: usize,
+    cached: Mutex<CachedColors>,
+}
 
-        assert_eq!(c, inner.material.color);
-    }
+#[derive(Debug, Default)]
+struct CachedColors {
+    entries: HashMap<[i64; 3], CachedColor>,
+    clock: u64,
+}
 
-    #[test]
-    fn there_is_no_shadow_when_nothing_is_collinear_with_point_and_light() {
-        let w = default_world();
-        let p = Tuple::new_point(0.0, 10.0, 0.0);
+#[derive(Debug, Copy, Clone)]
+struct CachedColor {
+    direction: [f64; 3],
+    color: Color,
+    last_used: u64,
+}
 
-        assert!(!w.is_shadowed(p));
+impl BackgroundCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            cached: Mutex::new(CachedColors::default()),
+        }
     }
 
-    #[test]
-    fn the_shadow_when_an_object_is_between_the_point_and_the_light() {
-        let w = default_world();
-        let p = Tuple::new_point(10.0, -10.0, 10.0);
+    pub fn len(&self) -> usize {
+        self.cached.lock().unwrap().entries.len()
+    }
 
-        assert!(w.is_shadowed(p));
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
-    #[test]
-    fn there_is_no_shadow_when_an_object_is_behind_the_light() {
-        let w = default_world();
-        let p = Tuple::new_point(-20.0, 20.0, -20.0);
+    pub fn color_at(&self, background: &Background, direction: Tuple) -> Color {
+        let unit = direction.normalize();
+        let key = [unit.x, unit.y, unit.z].map(|c| (c * BACKGROUND_CACHE_BUCKETS).round() as i64);
+        let exact = [direction.x, direction.y, direction.z];
+        let mut cached = self.cached.lock().unwrap();
+        cached.clock += 1;
+        let now = cached.clock;
+        if let Some(entry) = cached.entries.get_mut(&key) {
+            if entry.direction == exact {
+                entry.last_used = now;
+                return entry.color;
+            }
+        }
+        let color = background.color_at(direction);
+        if self.capacity == 0 {
+            return color;
+        }
+        if !cached.entries.contains_key(&key) && cached.entries.len() >= self.capacity {
+            let oldest = cached
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(&key, _)| key);
+            if let Some(oldest) = oldest {
+                cached.entries.remove(&oldest);
+            }
+        }
+        cached.entries.insert(
+            key,
+            CachedColor {
+                direction: exact,
+                color,
+                last_used: now,
+            },
+        );
+        color
+    }
+}
 
-        assert!(!w.is_shadowed(p));
+// A copy starts out empty.
+impl Clone for BackgroundCache {
+    fn clone(&self) -> Self {
+        BackgroundCache::new(self.capacity)
     }
+}
 
-    #[test]
-    fn there_is_no_shadow_when_an_object_is_behind_the_point() {
-        let w = default_world();
-        let p = Tuple::new_point(-2.0, 2.0, -2.0);
+// Setup mistakes that would render a blank or broken image. Shapes are identified by their index
+// in the world's objects.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SceneWarning {
+    NoLight,
+    SingularTransform(usize),
+    ZeroMaterial(usize),
+}
 
-        assert!(!w.is_shadowed(p));
+// Buffers handed from one primary ray to the next, so a render loop does not allocate a new
+// top-level list of intersections for every pixel. That is all it reuses: each shape still returns
+// its hits in a list of its own, and reflected, refracted and shadow rays get fresh lists too.
+pub struct RenderContext<'a, S: Shape> {
+    intersections: Vec<Intersection<'a, S>>,
+}
+
+impl<'a, S: Shape> RenderContext<'a, S> {
+    pub fn new() -> Self {
+        Self {
+            intersections: Vec::new(),
+        }
     }
+}
 
-    #[test]
-    fn shade_hit_is_given_an_intersection_in_shadow() {
+impl<'a, S: Shape> Default for RenderContext<'a, S> {
+    fn default() -> Self {
+        RenderContext::new()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct World<S: Shape = WorldShape> {
+    pub objects: Vec<S>,
+    pub light: Option<PointLight>,
+    pub occlusion: Option<AmbientOcclusion>,
+    pub background: Background,
+    // Off unless set.
+    pub background_cache: Option<BackgroundCache>,
+    pub contact_softness: f64,
+    // Reflected and refracted rays are only cast while they could still add this much to a pixel.
+    pub min_throughput: f64,
+    pub stats: Stats,
+}
+
+impl<S: Shape> World<S> {
+    pub fn new() -> Self {
+        Self {
+            objects: Vec::new(),
+            light: None,
+            occlusion: None,
+            background: Background::default(),
+            background_cache: None,
+            contact_softness: 0.0,
+            min_throughput: MIN_THROUGHPUT,
+            stats: Stats::new(),
+        }
+    }
+
+    // What a ray that misses everything sees, through the cache when there is one.
+    fn background_at(&self, direction: Tuple) -> Color {
+        match &self.background_cache {
+            Some(cache) => cache.color_at(&self.background, direction),
+            None => self.background.color_at(direction),
+        }
+    }
+
+    pub fn shapes(&self) -> impl Iterator<Item = &S> {
+        self.objects.iter()
+    }
+
+    // Normals need no special care: normal_at already goes through the inverse-transpose.
+    pub fn convert_handedness(&mut self) {
+        self.apply_transform(Matrix4::flip_handedness());
+    }
+
+    pub fn apply_transform(&mut self, m: Matrix4) {
+        for object in self.objects.iter_mut() {
+            let transform = m * *object.transform();
+            object.set_transform(transform);
+        }
+        if let Some(light) = self.light.as_mut() {
+            light.position = m * light.position;
+        }
+    }
+
+    pub fn intersect_world(&self, r: Ray) -> Intersections<'_, S> {
+        self.stats.record_intersection_tests(self.objects.len());
+        intersect_all(&self.objects, r)
+    }
+
+    pub fn intersect_world_with<'a>(
+        &'a self,
+        r: Ray,
+        context: &mut RenderContext<'a, S>,
+    ) -> Intersections<'a, S> {
+        self.stats.record_intersection_tests(self.objects.len());
+        let mut xs = mem::take(&mut context.intersections);
+        xs.clear();
+        for object in self.objects.iter() {
+            xs.extend(object.intersect(r).into_vec());
+        }
+        Intersections::new(xs)
+    }
+
+    // Finds the hit of every ray, reusing one buffer for the intersections of all of them.
+    pub fn intersect_batch(&self, rays: &[Ray]) -> Vec<Option<Intersection<'_, S>>> {
+        let capacity = self.objects.iter().map(|o| o.max_intersections()).sum();
+        let mut scratch = Vec::with_capacity(capacity);
+        rays.iter()
+            .map(|&r| {
+                self.stats.record_intersection_tests(self.objects.len());
+                scratch.clear();
+                for object in &self.objects {
+                    scratch.extend(object.intersect(r).iter().cloned());
+                }
+                scratch
+                    .iter()
+                    .filter(|x| x.t.is_sign_positive())
+                    .min_by(|lhs, rhs| lhs.t.partial_cmp(&rhs.t).unwrap())
+                    .cloned()
+            })
+            .collect()
+    }
+
+    pub fn shade_hit(&self, comps: Computations<S>, remaining: usize) -> Color {
+        self.shade_hit_weighted(comps, remaining, 1.0)
+    }
+
+    // throughput is the fraction of the shaded color that reaches the eye, after the reflections
+    // and refractions on the way there.
+    fn shade_hit_weighted(
+        &self,
+        comps: Computations<S>,
+        remaining: usize,
+        throughput: f64,
+    ) -> Color {
+        let visibility = self.light_visibility(&comps);
+        let occlusion = self.occlusion.map_or(1.0, |occlusion| {
+            self.ambient_occlusion(&comps, occlusion.samples, occlusion.radius)
+        });
+        let material = comps.object.material();
+        let color = comps.surface_color();
+        let shade = |in_shadow| {
+            material.lighting_with_ambient_occlusion(
+                color,
+                self.light.unwrap(),
+                comps.point,
+                comps.eyev,
+                comps.normalv,
+                in_shadow,
+                occlusion,
+            )
+        };
+        let surface = if visibility == 1.0 {
+            shade(false)
+        } else if visibility == 0.0 {
+            shade(true)
+        } else {
+            shade(false) * visibility + shade(true) * (1.0 - visibility)
+        };
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            let reflectance = comps.schlick();
+            let reflected =
+                self.reflected_color_weighted(&comps, remaining, throughput * reflectance);
+            let refracted =
+                self.refracted_color_weighted(&comps, remaining, throughput * (1.0 - reflectance));
+            surface + reflected * reflectance + refracted * (1.0 - reflectance)
+        } else {
+            surface
+                + self.reflected_color_weighted(&comps, remaining, throughput)
+                + self.refracted_color_weighted(&comps, remaining, throughput)
+        }
+    }
+
+    // remaining counts the reflections still allowed, so two facing mirrors end in black
+    // instead of recursing forever.
+    pub fn reflected_color(&self, comps: &Computations<S>, remaining: usize) -> Color {
+        self.reflected_color_weighted(comps, remaining, 1.0)
+    }
+
+    // The chain also ends once what it reflects is too faint to matter.
+    fn reflected_color_weighted(
+        &self,
+        comps: &Computations<S>,
+        remaining: usize,
+        throughput: f64,
+    ) -> Color {
+        let material = comps.object.material();
+        let reflective = material.reflective;
+        if remaining == 0 || reflective == 0.0 || throughput * reflective < self.min_throughput {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let roughness = material.reflection_roughness;
+        if roughness <= 0.0 {
+            self.stats.record_secondary_ray();
+            let reflect_ray = Ray::new(comps.over_point, comps.reflectv);
+            return self.trace(reflect_ray, remaining - 1, throughput * reflective) * reflective;
+        }
+        // A rough surface averages rays spread over a cone around the mirror direction, along a
+        // golden-angle spiral like the contact softness samples. Each carries only its share of
+        // the throughput, so the chains they start die out sooner.
+        let (tangent, bitangent) = tangent_basis(comps.reflectv);
+        let share = throughput * reflective / GLOSSY_SAMPLES as f64;
+        let total = (0..GLOSSY_SAMPLES).fold(Color::new(0.0, 0.0, 0.0), |sum, i| {
+            let radius = roughness * f64::sqrt((i as f64 + 0.5) / GLOSSY_SAMPLES as f64);
+            let phi = GOLDEN_ANGLE * i as f64;
+            let mut direction = (comps.reflectv
+                + tangent * (radius * phi.cos())
+                + bitangent * (radius * phi.sin()))
+            .normalize();
+            // Samples that would go into the surface are mirrored back out of it.
+            let into = direction * comps.normalv;
+            if into < 0.0 {
+                direction = direction - comps.normalv * (2.0 * into);
+            }
+            self.stats.record_secondary_ray();
+            sum + self.trace(Ray::new(comps.over_point, direction), remaining - 1, share)
+        });
+        total * (reflective / GLOSSY_SAMPLES as f64)
+    }
+
+    // Bends the ray into the surface by Snell's law. Past the critical angle all the light is
+    // reflected instead, so nothing comes through.
+    pub fn refracted_color(&self, comps: &Computations<S>, remaining: usize) -> Color {
+        self.refracted_color_weighted(comps, remaining, 1.0)
+    }
+
+    fn refracted_color_weighted(
+        &self,
+        comps: &Computations<S>,
+        remaining: usize,
+        throughput: f64,
+    ) -> Color {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let transparency = comps.object.material().transparency;
+        if remaining == 0 || transparency == 0.0 || throughput * transparency < self.min_throughput
+        {
+            return black;
+        }
+        let n_ratio = comps.n1 / comps.n2;
+        let cos_i = comps.eyev * comps.normalv;
+        let sin2_t = n_ratio.powi(2) * (1.0 - cos_i.powi(2));
+        if sin2_t > 1.0 {
+            return black;
+        }
+        self.stats.record_secondary_ray();
+        let cos_t = f64::sqrt(1.0 - sin2_t);
+        let direction = comps.normalv * (n_ratio * cos_i - cos_t) - comps.eyev * n_ratio;
+        let refract_ray = Ray::new(comps.under_point, direction);
+        self.trace(refract_ray, remaining - 1, throughput * transparency) * transparency
+    }
+
+    // The fraction of the light reaching the hit. With contact softness the shadow ray starts
+    // from points on a disk of that radius around the hit, spread along a golden-angle spiral.
+    // The disk is the same size everywhere, so every shadow edge gets a penumbra about that wide,
+    // whether it is near the occluder or far from it.
+    pub fn light_visibility(&self, comps: &Computations<S>) -> f64 {
+        let light = self.light.unwrap().position;
+        if self.contact_softness <= 0.0 {
+            return if self.is_occluded(comps.over_point, light) {
+                0.0
+            } else {
+                1.0
+            };
+        }
+        let (tangent, bitangent) = tangent_basis(comps.normalv);
+        let unoccluded = (0..CONTACT_SOFTNESS_SAMPLES)
+            .filter(|&i| {
+                let radius = self.contact_softness
+                    * f64::sqrt((i as f64 + 0.5) / CONTACT_SOFTNESS_SAMPLES as f64);
+                let phi = GOLDEN_ANGLE * i as f64;
+                let origin = comps.over_point
+                    + tangent * (radius * phi.cos())
+                    + bitangent * (radius * phi.sin());
+                !self.is_occluded(origin, light)
+            })
+            .count();
+        unoccluded as f64 / CONTACT_SOFTNESS_SAMPLES as f64
+    }
+
+    // Casts the samples over the hemisphere around the normal, spread along a golden-angle
+    // spiral so the result is deterministic and the directions cover the hemisphere evenly.
+    pub fn ambient_occlusion(&self, comps: &Computations<S>, samples: usize, radius: f64) -> f64 {
+        if samples == 0 {
+            return 1.0;
+        }
+        let normal = comps.normalv;
+        let (tangent, bitangent) = tangent_basis(normal);
+
+        let unoccluded = (0..samples)
+            .filter(|&i| {
+                let cos_theta = 1.0 - (i as f64 + 0.5) / samples as f64;
+                let sin_theta = f64::sqrt(1.0 - cos_theta.powi(2));
+                let phi = GOLDEN_ANGLE * i as f64;
+                let direction = tangent * (sin_theta * phi.cos())
+                    + bitangent * (sin_theta * phi.sin())
+                    + normal * cos_theta;
+                !self.is_occluded(comps.over_point, comps.over_point + direction * radius)
+            })
+            .count();
+        unoccluded as f64 / samples as f64
+    }
+
+    pub fn color_at(&self, r: Ray) -> Color {
+        self.stats.record_primary_ray();
+        self.color_at_depth(r, RECURSION_DEPTH)
+    }
+
+    pub fn color_at_depth(&self, r: Ray, remaining: usize) -> Color {
+        self.trace(r, remaining, 1.0)
+    }
+
+    fn trace(&self, r: Ray, remaining: usize, throughput: f64) -> Color {
+        let xs = self.intersect_world(r);
+        if let Some(hit) = xs.hit() {
+            self.shade_intersection(hit, r, &xs, remaining, throughput)
+        } else {
+            self.background_at(r.direction)
+        }
+    }
+
+    pub fn color_at_with<'a>(&'a self, r: Ray, context: &mut RenderContext<'a, S>) -> Color {
+        self.stats.record_primary_ray();
+        let xs = self.intersect_world_with(r, context);
+        let color = if let Some(hit) = xs.hit() {
+            self.shade_intersection(hit, r, &xs, RECURSION_DEPTH, 1.0)
+        } else {
+            self.background_at(r.direction)
+        };
+        context.intersections = xs.into_vec();
+        color
+    }
+
+    pub fn color_and_hit(&self, r: Ray) -> (Color, Option<usize>) {
+        self.stats.record_primary_ray();
+        let xs = self.intersect_world(r);
+        if let Some(hit) = xs.hit() {
+            let id = self
+                .objects
+                .iter()
+                .position(|object| object.includes(hit.object));
+            (
+                self.shade_intersection(hit, r, &xs, RECURSION_DEPTH, 1.0),
+                id,
+            )
+        } else {
+            (self.background_at(r.direction), None)
+        }
+    }
+
+    // Shows how deep reflections go: the tint for the number of mirrors the ray reflects off
+    // before it lands on something else or leaves the scene, or white if it is still reflecting
+    // after max bounces. A ray that misses everything is black.
+    pub fn color_at_debug_depth(&self, r: Ray, max: usize) -> Color {
+        let mut ray = r;
+        for bounces in 0..=max {
+            let xs = self.intersect_world(ray);
+            let Some(hit) = xs.hit() else {
+                return if bounces == 0 {
+                    Color::new(0.0, 0.0, 0.0)
+                } else {
+                    depth_tint(bounces)
+                };
+            };
+            let comps = hit.prepare_computations_with(ray, &xs);
+            if comps.object.material().reflective == 0.0 {
+                return depth_tint(bounces);
+            }
+            ray = Ray::new(comps.over_point, comps.reflectv);
+        }
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    // Only what the first surface a ray hits reflects, leaving out its own shading and whatever
+    // shows through it. Misses are black.
+    pub fn reflection_at(&self, r: Ray) -> Color {
+        self.stats.record_primary_ray();
+        let xs = self.intersect_world(r);
+        match xs.hit() {
+            Some(hit) => {
+                self.reflected_color(&hit.prepare_computations_with(r, &xs), RECURSION_DEPTH)
+            }
+            None => Color::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn shade_intersection(
+        &self,
+        hit: &Intersection<S>,
+        r: Ray,
+        xs: &[Intersection<S>],
+        remaining: usize,
+        throughput: f64,
+    ) -> Color {
+        let comps = hit.prepare_computations_with(r, xs);
+        #[cfg(feature = "nan-check")]
+        assert!(
+            comps.point.is_finite() && comps.normalv.is_finite() && comps.eyev.is_finite(),
+            "non-finite shading inputs at t = {} for ray {:?}: point {:?}, normal {:?}",
+            comps.t,
+            r,
+            comps.point,
+            comps.normalv
+        );
+        // Seen from behind, a single sided surface isn't there, so the ray goes on past it.
+        if comps.inside && comps.object.material().single_sided {
+            return match xs.iter().find(|x| x.t > hit.t) {
+                Some(next) => self.shade_intersection(next, r, xs, remaining, throughput),
+                None => self.background_at(r.direction),
+            };
+        }
+        self.shade_hit_weighted(comps, remaining, throughput)
+    }
+
+    pub fn validate(&self) -> Vec<SceneWarning> {
+        let mut warnings = Vec::new();
+        if self.light.is_none() {
+            warnings.push(SceneWarning::NoLight);
+        }
+        for (i, object) in self.objects.iter().enumerate() {
+            if !object.transform().invertible() {
+                warnings.push(SceneWarning::SingularTransform(i));
+            }
+            // A black surface can still show a pattern, glow, or pass on what it reflects or
+            // lets through.
+            let material = object.material();
+            let black = Color::new(0.0, 0.0, 0.0);
+            let unlit = material.color == black
+                || (material.ambient == 0.0 && material.diffuse == 0.0 && material.specular == 0.0);
+            if unlit
+                && material.pattern.is_none()
+                && material.emissive == black
+                && material.reflective == 0.0
+                && material.transparency == 0.0
+            {
+                warnings.push(SceneWarning::ZeroMaterial(i));
+            }
+        }
+        warnings
+    }
+
+    // Follows one random path of up to max_bounces diffuse bounces, adding up the emission it
+    // picks up on the way. Bounce directions are cosine weighted, so each bounce only scales the
+    // path by the surface albedo. Averaging many paths converges to the diffuse global
+    // illumination of the scene. Rays that escape pick up the background.
+    pub fn path_trace(&self, r: Ray, rng: &mut XorShift, max_bounces: usize) -> Color {
+        self.path_trace_with(r, rng, PathTermination::MaxBounces(max_bounces), None)
+    }
+
+    // With next_event, which should be the world's emitters, every bounce also samples the light
+    // and each emitter, and bounce rays stop picking up the emitters' emission so it is not
+    // counted twice. The point light is only seen this way, since rays cannot hit it.
+    pub fn path_trace_with(
+        &self,
+        r: Ray,
+        rng: &mut XorShift,
+        termination: PathTermination,
+        next_event: Option<&[Emitter<S>]>,
+    ) -> Color {
+        let max_bounces = match termination {
+            PathTermination::MaxBounces(max_bounces) => max_bounces,
+            PathTermination::RussianRoulette { .. } => usize::MAX,
+        };
+        let mut radiance = Color::new(0.0, 0.0, 0.0);
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut ray = r;
+        for bounce in 0..=max_bounces {
+            let xs = self.intersect_world(ray);
+            let Some(hit) = xs.hit() else {
+                radiance = radiance + throughput * self.background_at(ray.direction);
+                break;
+            };
+            let comps = hit.prepare_computations(ray);
+            let material = comps.object.material();
+            let sampled = next_event.is_some_and(|emitters| {
+                emitters
+                    .iter()
+                    .any(|emitter| ptr::eq(emitter.object, comps.object))
+            });
+            if bounce == 0 || !sampled {
+                radiance = radiance + throughput * material.emissive;
+            }
+            if bounce == max_bounces {
+                break;
+            }
+            throughput = throughput * (comps.surface_color() * material.diffuse);
+            if let Some(emitters) = next_event {
+                let direct =
+                    self.sample_direct_light(comps.over_point, comps.normalv, emitters, rng);
+                radiance = radiance + throughput * direct;
+            }
+            if let PathTermination::RussianRoulette { min_bounces } = termination {
+                if bounce >= min_bounces {
+                    // Capped below one so that paths between white surfaces still end.
+                    let survival = throughput
+                        .red
+                        .max(throughput.green)
+                        .max(throughput.blue)
+                        .min(ROULETTE_MAX_SURVIVAL);
+                    if rng.next_f64() >= survival {
+                        break;
+                    }
+                    throughput = throughput * (1.0 / survival);
+                }
+            }
+            ray = Ray::new(
+                comps.over_point,
+                cosine_weighted_direction(comps.normalv, rng),
+            );
+        }
+        radiance
+    }
+
+    // The emissive shapes whose surfaces can be sampled, including those inside groups. It walks
+    // the whole scene, so renders find them once and hand them to every path.
+    pub fn emitters(&self) -> Vec<Emitter<'_, S>> {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let mut emitters = Vec::new();
+        for object in self.objects.iter() {
+            object.visit_surfaces(Matrix4::identity(), &mut |surface, parent_transform| {
+                if surface.material().emissive != black && surface.local_surface_area().is_some() {
+                    emitters.push(Emitter {
+                        object: surface,
+                        parent_transform,
+                    });
+                }
+            });
+        }
+        emitters
+    }
+
+    // An estimate of the light arriving at a diffuse point from the point light and one random
+    // spot on each emitter, each weighted by the cosine at the point. The cosine weighted bounce
+    // divides by pi, so both kinds of light do too, and both fall off with the squared distance.
+    // Emitters glow on both sides, as they do when a ray hits them.
+    fn sample_direct_light(
+        &self,
+        point: Tuple,
+        normal: Tuple,
+        emitters: &[Emitter<S>],
+        rng: &mut XorShift,
+    ) -> Color {
+        let mut direct = Color::new(0.0, 0.0, 0.0);
+        if let Some(light) = self.light {
+            let to_light = light.position - point;
+            let cos = normal * to_light.normalize();
+            if cos > 0.0 && !self.is_occluded(point, light.position) {
+                direct = direct + light.intensity * (cos / (PI * (to_light * to_light)));
+            }
+        }
+        for emitter in emitters {
+            let (object, parent_transform) = (emitter.object, emitter.parent_transform);
+            let emissive = object.material().emissive;
+            let Some((sample, sample_normal)) =
+                object.sample_surface_within(parent_transform, rng.next_f64(), rng.next_f64())
+            else {
+                continue;
+            };
+            let Some(pdf) = object.sample_surface_pdf_within(parent_transform, sample) else {
+                continue;
+            };
+            let to_sample = sample - point;
+            let distance_squared = to_sample * to_sample;
+            let direction = to_sample.normalize();
+            let cos = normal * direction;
+            if cos <= 0.0 {
+                continue;
+            }
+            // Stop the shadow ray just short of the emitter so it does not block itself.
+            let offset = EPSILON * (1.0 + (sample - Tuple::new_point(0.0, 0.0, 0.0)).magnitude());
+            if self.is_occluded(point, sample - direction * offset) {
+                continue;
+            }
+            let cos_sample = (sample_normal * direction).abs();
+            direct = direct + emissive * (cos * cos_sample / (PI * distance_squared * pdf));
+        }
+        direct
+    }
+
+    // The light scattered towards the ray origin by the fog between t = 0 and t_max. Each step
+    // adds the light reaching its midpoint, dimmed by the fog between that point and the origin.
+    // Points in shadow add nothing, which is what makes light shafts visible.
+    pub fn integrate_volume(&self, r: Ray, t_max: f64, fog: Fog) -> Color {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let Some(light) = self.light else {
+            return black;
+        };
+        if fog.steps == 0 {
+            return black;
+        }
+        let dt = t_max / fog.steps as f64;
+        let length = r.direction.magnitude();
+
+        (0..fog.steps).fold(black, |sum, i| {
+            let t = (i as f64 + 0.5) * dt;
+            let point = r.position(t);
+            if self.is_occluded(point, light.position) {
+                return sum;
+            }
+            let distance = t * length;
+            let scattered = fog.density * dt * length * (-fog.density * distance).exp();
+            sum + light.intensity * scattered
+        })
+    }
+
+    pub fn is_shadowed(&self, point: Tuple) -> bool {
+        self.is_occluded(point, self.light.unwrap().position)
+    }
+
+    pub fn is_occluded(&self, from: Tuple, to: Tuple) -> bool {
+        let v = to - from;
+        let distance = v.magnitude();
+        let direction = v.normalize();
+
+        // With nothing that could block it, there is no point casting the ray at all.
+        if !self.objects.iter().any(|object| object.casts_shadow()) {
+            return false;
+        }
+        let r = Ray::new(from, direction);
+        self.stats.record_shadow_ray();
+        // Checked on each hit rather than each object, so shapes inside groups can opt out too.
+        self.objects.iter().any(|object| {
+            self.stats.record_intersection_tests(1);
+            object
+                .intersect(r)
+                .iter()
+                .any(|x| x.object.material().cast_shadow && 0.0 < x.t && x.t < distance)
+        })
+    }
+}
+
+impl<S: Shape> Default for World<S> {
+    fn default() -> Self {
+        World::new()
+    }
+}
+
+#[doc(hidden)]
+pub fn default_world() -> World<Sphere> {
+    let light = PointLight::new(
+        Tuple::new_point(-10.0, 10.0, -10.0),
+        Color::new(1.0, 1.0, 1.0),
+    );
+    let mut s1 = Sphere::new();
+    s1.material.color = Color::new(0.8, 1.0, 0.6);
+    s1.material.diffuse = 0.7;
+    s1.material.specular = 0.2;
+    let mut s2 = Sphere::new();
+    s2.transform = Matrix4::scaling(0.5, 0.5, 0.5);
+
+    World {
+        objects: vec![s1, s2],
+        light: Some(light),
+        occlusion: None,
+        background: Background::default(),
+        background_cache: None,
+        contact_softness: 0.0,
+        min_throughput: MIN_THROUGHPUT,
+        stats: Stats::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::csg::{Csg, Operation};
+    use crate::cube::Cube;
+    use crate::group::Group;
+    use crate::intersections::{Intersection, Intersections};
+    use crate::light::PointLight;
+    use crate::material::Material;
+    use crate::matrix::Matrix4;
+    use crate::pattern::SolidPattern;
+    use crate::plane::Plane;
+    use crate::random::XorShift;
+    use crate::ray::Ray;
+    use crate::shape::Shape;
+    use crate::sphere::Sphere;
+    use crate::testing::TestPattern;
+    use crate::triangle::Triangle;
+    use crate::tuple::Tuple;
+    use crate::world::{
+        default_world, depth_tint, AmbientOcclusion, Background, BackgroundCache, Fog,
+        PathTermination, RenderContext, SceneWarning, World, WorldShape, GLOSSY_SAMPLES,
+        RECURSION_DEPTH,
+    };
+    use crate::{assert_float_eq, EPSILON};
+    use std::f64::consts::PI;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn creating_a_world() {
+        let w: World = World::new();
+
+        assert!(w.objects.is_empty());
+        assert_eq!(w.light, None)
+    }
+
+    #[test]
+    fn the_default_world() {
+        let light = PointLight::new(
+            Tuple::new_point(-10.0, 10.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        );
+        let mut s1 = Sphere::new();
+        s1.material.color = Color::new(0.8, 1.0, 0.6);
+        s1.material.diffuse = 0.7;
+        s1.material.specular = 0.2;
+        let mut s2 = Sphere::new();
+        s2.transform = Matrix4::scaling(0.5, 0.5, 0.5);
+        let w = default_world();
+
+        assert_eq!(w.light, Some(light));
+        assert!(w.objects.contains(&s1));
+        assert!(w.objects.contains(&s2));
+    }
+
+    #[test]
+    fn iterating_the_shapes_of_the_default_world() {
+        let w = default_world();
+        let colors: Vec<_> = w.shapes().map(|s| s.material().color).collect();
+
+        assert_eq!(
+            colors,
+            vec![Color::new(0.8, 1.0, 0.6), Color::new(1.0, 1.0, 1.0)]
+        );
+    }
+
+    #[test]
+    fn naming_shapes() {
+        let mut sphere: WorldShape = Sphere::new().into();
+        let mut plane: WorldShape = Plane::new().into();
+
+        assert_eq!(sphere.name(), None);
+
+        sphere.set_name("ball");
+        plane.set_name("floor");
+
+        assert_eq!(sphere.name(), Some("ball"));
+        assert_eq!(plane.name(), Some("floor"));
+        assert_ne!(sphere.name(), plane.name());
+    }
+
+    #[test]
+    fn every_kind_of_shape_keeps_a_name_given_at_runtime() {
+        let mut shapes: Vec<WorldShape> = vec![
+            Group::new().into(),
+            Csg::new(Operation::Union, Sphere::new(), Plane::new()).into(),
+            Triangle::new(
+                Tuple::new_point(0.0, 1.0, 0.0),
+                Tuple::new_point(-1.0, 0.0, 0.0),
+                Tuple::new_point(1.0, 0.0, 0.0),
+            )
+            .into(),
+        ];
+        for (i, shape) in shapes.iter_mut().enumerate() {
+            shape.set_name(format!("mesh {}", i));
+        }
+
+        for (i, shape) in shapes.iter().enumerate() {
+            assert_eq!(shape.name(), Some(format!("mesh {}", i).as_str()));
+        }
+    }
+
+    #[test]
+    fn finding_a_shape_by_name() {
+        let mut w = default_world();
+        w.objects[1].set_name("inner");
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let (_, id) = w.color_and_hit(r);
+
+        assert_eq!(w.objects[id.unwrap()].name(), Some("inner"));
+    }
+
+    #[test]
+    fn world_shapes_forward_their_intersection_hint() {
+        let sphere: WorldShape = Sphere::new().into();
+        let plane: WorldShape = Plane::new().into();
+
+        assert_eq!(sphere.max_intersections(), 2);
+        assert_eq!(plane.max_intersections(), 1);
+    }
+
+    #[test]
+    fn intersect_a_world_with_a_ray() {
+        let w = default_world();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = w.intersect_world(r);
+
+        assert_eq!(xs.len(), 4);
+        assert_float_eq!(xs[0].t, 4.0);
+        assert_float_eq!(xs[1].t, 4.5);
+        assert_float_eq!(xs[2].t, 5.5);
+        assert_float_eq!(xs[3].t, 6.0);
+    }
+
+    #[test]
+    fn shading_an_intersection() {
+        let w = default_world();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let shape = &w.objects[0];
+        let i = Intersection::new(4.0, shape);
+        let comps = i.prepare_computations(r);
+        let c = w.shade_hit(comps, RECURSION_DEPTH);
+
+        assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn shading_an_intersection_from_the_inside() {
+        let mut w = default_world();
+        w.light = Some(PointLight::new(
+            Tuple::new_point(0.0, 0.25, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let shape = &w.objects[1];
+        let i = Intersection::new(0.5, shape);
+        let comps = i.prepare_computations(r);
+        let c = w.shade_hit(comps, RECURSION_DEPTH);
+
+        assert_eq!(c, Color::new(0.90498, 0.90498, 0.90498));
+    }
+
+    #[test]
+    fn the_color_when_a_ray_misses() {
+        let w = default_world();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let c = w.color_at(r);
+
+        assert_eq!(c, Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn the_color_when_a_ray_hits() {
+        let w = default_world();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let c = w.color_at(r);
+
+        assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn picking_the_front_sphere_of_the_default_world() {
+        let w = default_world();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let (color, id) = w.color_and_hit(r);
+
+        assert_eq!(color, w.color_at(r));
+        assert_eq!(id, Some(0));
+    }
+
+    #[test]
+    fn picking_a_shape_inside_a_group_reports_the_group() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        let mut w: World = World::new();
+        w.light = default_world().light;
+        w.objects.push(Plane::new().into());
+        w.objects.push(g.into());
+        let r = Ray::new(
+            Tuple::new_point(0.0, 1.0, -5.0),
+            Tuple::new_vector(0.0, -0.1, 1.0),
+        );
+        let (color, id) = w.color_and_hit(r);
+
+        assert_eq!(color, w.color_at(r));
+        assert_eq!(id, Some(1));
+    }
+
+    #[test]
+    fn picking_reports_no_shape_when_the_ray_misses() {
+        let w = default_world();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+
+        assert_eq!(w.color_and_hit(r), (Color::new(0.0, 0.0, 0.0), None));
+    }
+
+    #[test]
+    fn the_color_with_an_intersection_behind_the_ray() {
+        let mut w = default_world();
+        let inner = {
+            let outer = &mut w.objects[0];
+            outer.material.ambient = 1.0;
+            let inner = &mut w.objects[1];
+            inner.material.ambient = 1.0;
+            inner.clone()
+        };
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, 0.75),
+            Tuple::new_vector(0.0, 0.0, -1.0),
+        );
+        let c = w.color_at(r);
+
+        assert_eq!(c, inner.material.color);
+    }
+
+    fn default_world_with(shape: WorldShape) -> World {
+        let default = default_world();
+        let mut w = World::new();
+        w.light = default.light;
+        w.objects = default.objects.into_iter().map(WorldShape::from).collect();
+        w.objects.push(shape);
+        w
+    }
+
+    fn reflective_floor() -> Plane {
+        let mut shape = Plane::new();
+        shape.material.reflective = 0.5;
+        shape.transform = Matrix4::translation(0.0, -1.0, 0.0);
+        shape
+    }
+
+    #[test]
+    fn the_reflected_color_for_a_nonreflective_material() {
+        let mut w = default_world();
+        w.objects[1].material.ambient = 1.0;
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let i = Intersection::new(1.0, &w.objects[1]);
+        let comps = i.prepare_computations(r);
+
+        assert_eq!(
+            w.reflected_color(&comps, RECURSION_DEPTH),
+            Color::new(0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn the_reflected_color_for_a_reflective_material() {
+        let w = default_world_with(reflective_floor().into());
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -3.0),
+            Tuple::new_vector(0.0, -f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0),
+        );
+        let i = Intersection::new(f64::sqrt(2.0), &w.objects[2]);
+        let comps = i.prepare_computations(r);
+        let c = w.reflected_color(&comps, RECURSION_DEPTH);
+
+        assert!((c.red - 0.19032).abs() < 0.0001);
+        assert!((c.green - 0.2379).abs() < 0.0001);
+        assert!((c.blue - 0.14274).abs() < 0.0001);
+        assert_eq!(w.stats.secondary_rays(), 1);
+    }
+
+    #[test]
+    fn the_background_cache_only_answers_for_the_exact_direction() {
+        let background = Background::Gradient {
+            bottom: Color::new(0.0, 0.0, 0.0),
+            top: Color::new(1.0, 1.0, 1.0),
+        };
+        let cache = BackgroundCache::new(2);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        let nearly_up = Tuple::new_vector(0.0, 1.0, 1e-6);
+
+        assert!(cache.is_empty());
+        assert_eq!(cache.color_at(&background, up), background.color_at(up));
+        assert_eq!(cache.color_at(&background, up), background.color_at(up));
+        assert_eq!(cache.len(), 1);
+        let c = cache.color_at(&background, nearly_up);
+        assert_eq!(c.red, background.color_at(nearly_up).red);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn the_background_cache_drops_the_entry_used_longest_ago() {
+        let background = Background::Solid(Color::new(0.5, 0.5, 0.5));
+        let cache = BackgroundCache::new(2);
+        for direction in [
+            Tuple::new_vector(1.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        ] {
+            cache.color_at(&background, direction);
+        }
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.clone().len(), 0);
+    }
+
+    #[test]
+    fn zero_roughness_reflects_like_a_mirror() {
+        let mut floor = reflective_floor();
+        floor.material.reflection_roughness = 0.0;
+        let w = default_world_with(floor.into());
+        let mirror = default_world_with(reflective_floor().into());
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -3.0),
+            Tuple::new_vector(0.0, -f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0),
+        );
+        let c = w.reflected_color(
+            &Intersection::new(f64::sqrt(2.0), &w.objects[2]).prepare_computations(r),
+            RECURSION_DEPTH,
+        );
+        let expected = mirror.reflected_color(
+            &Intersection::new(f64::sqrt(2.0), &mirror.objects[2]).prepare_computations(r),
+            RECURSION_DEPTH,
+        );
+
+        assert_eq!(c.red, expected.red);
+        assert_eq!(c.green, expected.green);
+        assert_eq!(c.blue, expected.blue);
+        assert_eq!(w.stats.secondary_rays(), 1);
+    }
+
+    #[test]
+    fn rough_reflections_average_several_rays() {
+        let mut floor = reflective_floor();
+        floor.material.reflection_roughness = 0.3;
+        let w = default_world_with(floor.into());
+        let mirror = default_world_with(reflective_floor().into());
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -3.0),
+            Tuple::new_vector(0.0, -f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0),
+        );
+        let c = w.reflected_color(
+            &Intersection::new(f64::sqrt(2.0), &w.objects[2]).prepare_computations(r),
+            RECURSION_DEPTH,
+        );
+        let sharp = mirror.reflected_color(
+            &Intersection::new(f64::sqrt(2.0), &mirror.objects[2]).prepare_computations(r),
+            RECURSION_DEPTH,
+        );
+
+        assert_ne!(c, sharp);
+        assert_ne!(c, Color::new(0.0, 0.0, 0.0));
+        assert_eq!(w.stats.secondary_rays(), GLOSSY_SAMPLES as u64);
+    }
+
+    #[test]
+    fn shade_hit_with_a_reflective_material() {
+        let w = default_world_with(reflective_floor().into());
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -3.0),
+            Tuple::new_vector(0.0, -f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0),
+        );
+        let i = Intersection::new(f64::sqrt(2.0), &w.objects[2]);
+        let comps = i.prepare_computations(r);
+        let c = w.shade_hit(comps, RECURSION_DEPTH);
+
+        assert!((c.red - 0.87677).abs() < 0.0001);
+        assert!((c.green - 0.92436).abs() < 0.0001);
+        assert!((c.blue - 0.82918).abs() < 0.0001);
+    }
+
+    #[test]
+    fn faint_reflections_end_before_the_depth_limit() {
+        let mut w: World = World::new();
+        w.light = default_world().light;
+        for y in [-1.0, 1.0] {
+            let mut plane = Plane::new();
+            plane.transform = Matrix4::translation(0.0, y, 0.0);
+            plane.material.color = Color::new(0.01, 0.01, 0.01);
+            plane.material.ambient = 1.0;
+            plane.material.diffuse = 0.0;
+            plane.material.specular = 0.0;
+            plane.material.reflective = 0.02;
+            w.objects.push(plane.into());
+        }
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, -1.0, 1.0).normalize(),
+        );
+        let mut unlimited = w.clone();
+        unlimited.min_throughput = 0.0;
+        let c = w.color_at(r);
+        let expected = unlimited.color_at(r);
+
+        assert_eq!(c, expected);
+        assert_eq!(w.stats.secondary_rays(), 1);
+        assert_eq!(unlimited.stats.secondary_rays(), RECURSION_DEPTH as u64);
+    }
+
+    #[test]
+    fn debug_depth_tints_each_bounce_between_two_mirrors() {
+        let mut w: World = World::new();
+        w.light = default_world().light;
+        for y in [-1.0, 1.0] {
+            let mut mirror = Cube::new();
+            mirror.transform = Matrix4::identity()
+                .scale(10.0, 0.01, 2.0)
+                .translate(0.0, y, 2.0);
+            mirror.material.reflective = 1.0;
+            w.objects.push(mirror.into());
+        }
+        // Rising one unit every run along z, the ray reflects off a mirror every 2 * run
+        // until it passes z = 4.
+        let ray = |run: f64| {
+            Ray::new(
+                Tuple::new_point(0.0, 0.0, 0.0),
+                Tuple::new_vector(0.0, -1.0, run).normalize(),
+            )
+        };
+        let tints = [3.0, 1.0, 0.7].map(|run| w.color_at_debug_depth(ray(run), 3));
+
+        assert_eq!(
+            w.color_at_debug_depth(ray(5.0), 3),
+            Color::new(0.0, 0.0, 0.0)
+        );
+        assert_eq!(tints, [depth_tint(1), depth_tint(2), depth_tint(3)]);
+        assert_ne!(tints[0], tints[1]);
+        assert_ne!(tints[1], tints[2]);
+        assert_ne!(tints[0], tints[2]);
+        assert_eq!(
+            w.color_at_debug_depth(ray(0.5), 3),
+            Color::new(1.0, 1.0, 1.0)
+        );
+        assert_eq!(
+            w.color_at_debug_depth(ray(0.1), 3),
+            Color::new(1.0, 1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn color_at_with_mutually_reflective_surfaces() {
+        let mut lower = Plane::new();
+        lower.material.reflective = 1.0;
+        lower.transform = Matrix4::translation(0.0, -1.0, 0.0);
+        let mut upper = Plane::new();
+        upper.material.reflective = 1.0;
+        upper.transform = Matrix4::translation(0.0, 1.0, 0.0);
+        let mut w = World::new();
+        w.light = Some(PointLight::new(
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        w.objects.push(lower);
+        w.objects.push(upper);
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+
+        assert!(w.color_at(r).is_finite());
+        assert_eq!(w.stats.secondary_rays(), RECURSION_DEPTH as u64);
+    }
+
+    #[test]
+    fn the_refracted_color_with_an_opaque_surface() {
+        let w = default_world();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let shape = &w.objects[0];
+        let xs = Intersections::new(vec![
+            Intersection::new(4.0, shape),
+            Intersection::new(6.0, shape),
+        ]);
+        let comps = xs[0].prepare_computations_with(r, &xs);
+
+        assert_eq!(
+            w.refracted_color(&comps, RECURSION_DEPTH),
+            Color::new(0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn the_refracted_color_at_the_maximum_recursive_depth() {
+        let mut w = default_world();
+        w.objects[0].material.transparency = 1.0;
+        w.objects[0].material.refractive_index = 1.5;
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let shape = &w.objects[0];
+        let xs = Intersections::new(vec![
+            Intersection::new(4.0, shape),
+            Intersection::new(6.0, shape),
+        ]);
+        let comps = xs[0].prepare_computations_with(r, &xs);
+
+        assert_eq!(w.refracted_color(&comps, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn the_refracted_color_under_total_internal_reflection() {
+        let mut w = default_world();
+        w.objects[0].material.transparency = 1.0;
+        w.objects[0].material.refractive_index = 1.5;
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, f64::sqrt(2.0) / 2.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let shape = &w.objects[0];
+        let xs = Intersections::new(vec![
+            Intersection::new(-f64::sqrt(2.0) / 2.0, shape),
+            Intersection::new(f64::sqrt(2.0) / 2.0, shape),
+        ]);
+        // Inside the sphere, so look at the second intersection.
+        let comps = xs[1].prepare_computations_with(r, &xs);
+
+        assert_eq!(
+            w.refracted_color(&comps, RECURSION_DEPTH),
+            Color::new(0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn the_refracted_color_with_a_refracted_ray() {
+        let mut w = default_world();
+        w.objects[0].material.ambient = 1.0;
+        w.objects[0].material.pattern = Some(Box::new(TestPattern::new()));
+        w.objects[1].material.transparency = 1.0;
+        w.objects[1].material.refractive_index = 1.5;
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, 0.1),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let (a, b) = (&w.objects[0], &w.objects[1]);
+        let xs = Intersections::new(vec![
+            Intersection::new(-0.9899, a),
+            Intersection::new(-0.4899, b),
+            Intersection::new(0.4899, b),
+            Intersection::new(0.9899, a),
+        ]);
+        let comps = xs[2].prepare_computations_with(r, &xs);
+        let c = w.refracted_color(&comps, RECURSION_DEPTH);
+
+        assert!((c.red - 0.0).abs() < 0.0001);
+        assert!((c.green - 0.99888).abs() < 0.0001);
+        assert!((c.blue - 0.04725).abs() < 0.0001);
+    }
+
+    #[test]
+    fn shade_hit_with_a_transparent_material() {
+        let mut floor = Plane::new();
+        floor.transform = Matrix4::translation(0.0, -1.0, 0.0);
+        floor.material.transparency = 0.5;
+        floor.material.refractive_index = 1.5;
+        let mut ball = Sphere::new();
+        ball.material.color = Color::new(1.0, 0.0, 0.0);
+        ball.material.ambient = 0.5;
+        ball.transform = Matrix4::translation(0.0, -3.5, -0.5);
+        let mut w = default_world_with(floor.into());
+        w.objects.push(ball.into());
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -3.0),
+            Tuple::new_vector(0.0, -f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0),
+        );
+        let xs = Intersections::new(vec![Intersection::new(f64::sqrt(2.0), &w.objects[2])]);
+        let comps = xs[0].prepare_computations_with(r, &xs);
+        let c = w.shade_hit(comps, RECURSION_DEPTH);
+
+        assert!((c.red - 0.93642).abs() < 0.0001);
+        assert!((c.green - 0.68642).abs() < 0.0001);
+        assert!((c.blue - 0.68642).abs() < 0.0001);
+    }
+
+    #[test]
+    fn shade_hit_with_a_reflective_transparent_material() {
+        let mut floor = Plane::new();
+        floor.transform = Matrix4::translation(0.0, -1.0, 0.0);
+        floor.material.reflective = 0.5;
+        floor.material.transparency = 0.5;
+        floor.material.refractive_index = 1.5;
+        let mut ball = Sphere::new();
+        ball.material.color = Color::new(1.0, 0.0, 0.0);
+        ball.material.ambient = 0.5;
+        ball.transform = Matrix4::translation(0.0, -3.5, -0.5);
+        let mut w = default_world_with(floor.into());
+        w.objects.push(ball.into());
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -3.0),
+            Tuple::new_vector(0.0, -f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0),
+        );
+        let xs = Intersections::new(vec![Intersection::new(f64::sqrt(2.0), &w.objects[2])]);
+        let comps = xs[0].prepare_computations_with(r, &xs);
+        let c = w.shade_hit(comps, RECURSION_DEPTH);
+
+        assert!((c.red - 0.93391).abs() < 0.0001);
+        assert!((c.green - 0.69643).abs() < 0.0001);
+        assert!((c.blue - 0.69243).abs() < 0.0001);
+    }
+
+    #[test]
+    fn the_reflected_color_at_the_maximum_recursive_depth() {
+        let w = default_world_with(reflective_floor().into());
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -3.0),
+            Tuple::new_vector(0.0, -f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0),
+        );
+        let i = Intersection::new(f64::sqrt(2.0), &w.objects[2]);
+        let comps = i.prepare_computations(r);
+
+        assert_eq!(w.reflected_color(&comps, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn a_single_sided_plane_is_invisible_from_behind() {
+        let mut w: World<Plane> = World::new();
+        w.light = Some(PointLight::new(
+            Tuple::new_point(0.0, 10.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        w.objects.push(Plane::new());
+        let below = Ray::new(
+            Tuple::new_point(0.0, -1.0, 0.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let above = Ray::new(
+            Tuple::new_point(0.0, 1.0, 0.0),
+            Tuple::new_vector(0.0, -1.0, 0.0),
+        );
+
+        assert_eq!(w.color_at(below), Color::new(0.1, 0.1, 0.1));
+
+        w.objects[0].material.single_sided = true;
+
+        assert_eq!(w.color_at(below), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(w.color_at(above), Color::new(1.9, 1.9, 1.9));
+
+        w.background = Background::Solid(Color::new(0.2, 0.3, 0.4));
+
+        assert_eq!(w.color_at(below), Color::new(0.2, 0.3, 0.4));
+    }
+
+    #[test]
+    fn rays_pass_through_a_single_sided_surface_to_what_is_behind_it() {
+        let mut w: World = World::new();
+        w.light = default_world().light;
+        for s in default_world().objects {
+            w.objects.push(s.into());
+        }
+        let mut screen = Plane::new();
+        screen.transform = Matrix4::translation(0.0, 0.0, -3.0) * Matrix4::rotation_x(PI / 2.0);
+        screen.material.single_sided = true;
+        screen.material.cast_shadow = false;
+        w.objects.push(screen.into());
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+
+        assert_eq!(w.color_at(r), default_world().color_at(r));
+    }
+
+    #[test]
+    fn there_is_no_shadow_when_nothing_is_collinear_with_point_and_light() {
+        let w = default_world();
+        let p = Tuple::new_point(0.0, 10.0, 0.0);
+
+        assert!(!w.is_shadowed(p));
+    }
+
+    #[test]
+    fn the_shadow_when_an_object_is_between_the_point_and_the_light() {
+        let w = default_world();
+        let p = Tuple::new_point(10.0, -10.0, 10.0);
+
+        assert!(w.is_shadowed(p));
+    }
+
+    #[test]
+    fn objects_that_do_not_cast_shadows_leave_the_point_lit() {
+        let mut w = default_world();
+        let p = Tuple::new_point(10.0, -10.0, 10.0);
+        for object in w.objects.iter_mut() {
+            object.material.cast_shadow = false;
+        }
+
+        assert!(!w.is_shadowed(p));
+    }
+
+    #[test]
+    fn shapes_inside_groups_can_opt_out_of_casting_shadows() {
+        let mut s = Sphere::new();
+        s.material.cast_shadow = false;
+        let mut g = Group::new();
+        g.add_child(s);
+        let mut w: World = World::new();
+        w.light = default_world().light;
+        w.objects.push(g.into());
+
+        assert!(!w.is_shadowed(Tuple::new_point(10.0, -10.0, 10.0)));
+        assert_eq!(w.stats.shadow_rays(), 0);
+    }
+
+    #[test]
+    fn there_is_no_shadow_when_an_object_is_behind_the_light() {
+        let w = default_world();
+        let p = Tuple::new_point(-20.0, 20.0, -20.0);
+
+        assert!(!w.is_shadowed(p));
+    }
+
+    #[test]
+    fn there_is_no_shadow_when_an_object_is_behind_the_point() {
+        let w = default_world();
+        let p = Tuple::new_point(-2.0, 2.0, -2.0);
+
+        assert!(!w.is_shadowed(p));
+    }
+
+    #[test]
+    fn is_occluded_agrees_with_the_full_intersection_list() {
+        let w = default_world();
+        let light = w.light.unwrap().position;
+        let points = [
+            Tuple::new_point(0.0, 10.0, 0.0),
+            Tuple::new_point(10.0, -10.0, 10.0),
+            Tuple::new_point(-20.0, 20.0, -20.0),
+            Tuple::new_point(-2.0, 2.0, -2.0),
+        ];
+
+        for p in points {
+            let v = light - p;
+            let xs = w.intersect_world(Ray::new(p, v.normalize()));
+            let expected = xs.hit().is_some_and(|h| h.t < v.magnitude());
+
+            assert_eq!(w.is_occluded(p, light), expected);
+            assert_eq!(w.is_shadowed(p), expected);
+        }
+    }
+
+    // Timings for the benchmarks below, which are ignored by default. Run them with
+    // cargo test --release -- --ignored --nocapture.
+    fn time<T>(runs: usize, mut f: impl FnMut() -> T) -> Duration {
+        let start = Instant::now();
+        for _ in 0..runs {
+            std::hint::black_box(f());
+        }
+        start.elapsed()
+    }
+
+    fn row_of_spheres(count: usize) -> World<Sphere> {
+        let mut w = World::new();
+        w.light = default_world().light;
+        for i in 0..count {
+            let mut s = Sphere::new();
+            s.transform = Matrix4::translation(0.0, 0.0, 3.0 * i as f64);
+            w.objects.push(s);
+        }
+        w
+    }
+
+    #[test]
+    #[ignore]
+    fn benchmark_is_occluded_against_the_full_intersection_list() {
+        let w = row_of_spheres(1000);
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+        let to = Tuple::new_point(0.0, 0.0, 3005.0);
+        let full = time(1000, || {
+            let v = to - from;
+            let xs = w.intersect_world(Ray::new(from, v.normalize()));
+            xs.hit().is_some_and(|h| h.t < v.magnitude())
+        });
+        let early_out = time(1000, || w.is_occluded(from, to));
+        println!("full list: {:?}, is_occluded: {:?}", full, early_out);
+
+        assert!(early_out < full);
+    }
+
+    #[test]
+    #[ignore]
+    fn benchmark_preallocating_intersections_on_a_1000_sphere_world() {
+        let w = row_of_spheres(1000);
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        // Intersecting the spheres costs the same either way, so it is done once up front and
+        // only the merging into one list is timed. Growing the list doubles its capacity, so it
+        // only reallocates about a dozen times here, and the two come out about even.
+        let per_object: Vec<_> = w
+            .objects
+            .iter()
+            .map(|o| o.intersect(r).into_vec())
+            .collect();
+        let capacity = w.objects.iter().map(|o| o.max_intersections()).sum();
+        let growing = time(10000, || {
+            let mut xs = Vec::new();
+            for object_xs in &per_object {
+                xs.extend_from_slice(object_xs);
+            }
+            xs
+        });
+        let preallocated = time(10000, || {
+            let mut xs = Vec::with_capacity(capacity);
+            for object_xs in &per_object {
+                xs.extend_from_slice(object_xs);
+            }
+            xs
+        });
+        println!("growing: {:?}, preallocated: {:?}", growing, preallocated);
+    }
+
+    #[test]
+    #[ignore]
+    // Only the top-level list is reused, and shading costs far more than allocating it, so the
+    // gain is a few percent at most.
+    fn benchmark_reusing_the_intersection_list_across_rays() {
+        let w = row_of_spheres(100);
+        let rays: Vec<_> = (0..2500)
+            .map(|i| {
+                let x = (i % 50) as f64 / 25.0 - 1.0;
+                let y = (i / 50) as f64 / 25.0 - 1.0;
+                Ray::new(
+                    Tuple::new_point(x, y, -5.0),
+                    Tuple::new_vector(0.0, 0.0, 1.0),
+                )
+            })
+            .collect();
+        let fresh = time(4, || rays.iter().map(|&r| w.color_at(r).red).sum::<f64>());
+        let mut context = RenderContext::new();
+        let reused = time(4, || {
+            rays.iter()
+                .map(|&r| w.color_at_with(r, &mut context).red)
+                .sum::<f64>()
+        });
+        println!("fresh lists: {:?}, reused list: {:?}", fresh, reused);
+    }
+
+    #[test]
+    fn is_occluded_ignores_objects_beyond_the_target() {
+        let w = default_world();
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+
+        assert!(!w.is_occluded(from, Tuple::new_point(0.0, 0.0, -3.0)));
+        assert!(w.is_occluded(from, Tuple::new_point(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn shade_hit_is_given_an_intersection_in_shadow() {
         let mut w = World::new();
         w.light = Some(PointLight::new(
             Tuple::new_point(0.0, 0.0, -10.0),
@@ -341,14 +2057,14 @@ mod tests {
         w.objects.push(s1);
         let mut s2 = Sphere::new();
         s2.transform = Matrix4::translation(0.0, 0.0, 10.0);
-        w.objects.push(s2);
+        w.objects.push(s2.clone());
         let r = Ray::new(
             Tuple::new_point(0.0, 0.0, 5.0),
             Tuple::new_vector(0.0, 0.0, 1.0),
         );
         let i = Intersection::new(4.0, &s2);
         let comps = i.prepare_computations(r);
-        let c = w.shade_hit(comps);
+        let c = w.shade_hit(comps, RECURSION_DEPTH);
 
         assert_eq!(c, Color::new(0.1, 0.1, 0.1));
     }
@@ -367,4 +2083,580 @@ mod tests {
         assert!(comps.over_point.z < -EPSILON / 2.0);
         assert!(comps.point.z > comps.over_point.z);
     }
+
+    #[test]
+    fn coloring_with_a_render_context_matches_coloring_without() {
+        let w = default_world();
+        let mut context = RenderContext::new();
+        for i in 0..20 {
+            let angle = i as f64 * 0.02 - 0.2;
+            let r = Ray::new(
+                Tuple::new_point(0.0, 0.0, -5.0),
+                Tuple::new_vector(angle, angle / 2.0, 1.0).normalize(),
+            );
+            let xs = w.intersect_world(r);
+            let ts: Vec<_> = xs.iter().map(|x| x.t).collect();
+            let context_xs = w.intersect_world_with(r, &mut context);
+            let context_ts: Vec<_> = context_xs.iter().map(|x| x.t).collect();
+
+            assert_eq!(context_ts, ts);
+            assert_eq!(w.color_at_with(r, &mut context), w.color_at(r));
+        }
+    }
+
+    #[test]
+    fn scaling_the_whole_world() {
+        let mut w = default_world();
+        w.apply_transform(Matrix4::scaling(2.0, 2.0, 2.0));
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -10.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = w.intersect_world(r);
+        let ts: Vec<_> = xs.iter().map(|x| x.t).collect();
+
+        assert_eq!(ts, vec![8.0, 9.0, 11.0, 12.0]);
+        assert_eq!(
+            w.light.unwrap().position,
+            Tuple::new_point(-20.0, 20.0, -20.0)
+        );
+    }
+
+    #[test]
+    fn converting_the_handedness_of_a_world() {
+        let mut w = default_world();
+        w.objects[0].transform = Matrix4::translation(1.0, 2.0, 3.0);
+        w.convert_handedness();
+
+        assert_eq!(
+            w.objects[0].transform * Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_point(1.0, 2.0, -3.0)
+        );
+        assert_eq!(
+            w.light.unwrap().position,
+            Tuple::new_point(-10.0, 10.0, 10.0)
+        );
+
+        w.convert_handedness();
+
+        assert_eq!(w.objects[0].transform, Matrix4::translation(1.0, 2.0, 3.0));
+        assert_eq!(w.objects[1].transform, Matrix4::scaling(0.5, 0.5, 0.5));
+        assert_eq!(w.light, default_world().light);
+    }
+
+    #[test]
+    fn transforming_the_world_keeps_the_image() {
+        let w = default_world();
+        let m = Matrix4::rotation_y(0.5).translate(1.0, 2.0, 3.0);
+        let mut moved = default_world();
+        moved.apply_transform(m);
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.1, 0.0, 1.0).normalize(),
+        );
+        let moved_ray = Ray::new(m * r.origin, m * r.direction);
+
+        assert_eq!(moved.color_at(moved_ray), w.color_at(r));
+    }
+
+    #[test]
+    fn intersecting_a_batch_of_rays_matches_intersecting_each_ray() {
+        let w = default_world();
+        let origin = Tuple::new_point(0.0, 0.0, -5.0);
+        let rays = [
+            Ray::new(origin, Tuple::new_vector(0.0, 0.0, 1.0)),
+            Ray::new(origin, Tuple::new_vector(0.0, 1.0, 0.0)),
+            Ray::new(
+                Tuple::new_point(0.0, 0.0, 0.0),
+                Tuple::new_vector(0.0, 0.0, 1.0),
+            ),
+            Ray::new(origin, Tuple::new_vector(0.1, 0.1, 1.0).normalize()),
+        ];
+        let batch = w.intersect_batch(&rays);
+
+        assert_eq!(batch.len(), rays.len());
+        for (hit, &r) in batch.iter().zip(rays.iter()) {
+            let xs = w.intersect_world(r);
+            let expected = xs.hit();
+            assert_eq!(hit.map(|x| x.t), expected.map(|x| x.t));
+            assert_eq!(
+                hit.map(|x| x.object as *const Sphere),
+                expected.map(|x| x.object as *const Sphere)
+            );
+        }
+        assert!(batch[1].is_none());
+        assert_eq!(batch[2].unwrap().t, 0.5);
+    }
+
+    #[test]
+    fn a_missed_ray_sees_a_solid_background() {
+        let mut w = default_world();
+        w.background = Background::Solid(Color::new(0.2, 0.3, 0.4));
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+
+        assert_eq!(w.color_at(r), Color::new(0.2, 0.3, 0.4));
+        assert_eq!(w.color_and_hit(r), (Color::new(0.2, 0.3, 0.4), None));
+    }
+
+    #[test]
+    fn missed_rays_see_a_gradient_background() {
+        let mut w = default_world();
+        let bottom = Color::new(1.0, 1.0, 1.0);
+        let top = Color::new(0.5, 0.7, 1.0);
+        w.background = Background::Gradient { bottom, top };
+        let origin = Tuple::new_point(0.0, 0.0, -5.0);
+        let up = Ray::new(origin, Tuple::new_vector(0.0, 1.0, 0.0));
+        let down = Ray::new(origin, Tuple::new_vector(0.0, -1.0, 0.0));
+        let level = Ray::new(origin, Tuple::new_vector(0.0, 0.0, -1.0));
+
+        assert_eq!(w.color_at(up), top);
+        assert_eq!(w.color_at(down), bottom);
+        assert_eq!(w.color_at(level), Color::new(0.75, 0.85, 1.0));
+    }
+
+    fn sphere_under_a_light() -> World<Sphere> {
+        let mut w = World::new();
+        w.light = Some(PointLight::new(
+            Tuple::new_point(0.0, 10.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let mut s = Sphere::new();
+        s.transform = Matrix4::translation(0.0, 5.0, 0.0);
+        w.objects.push(s);
+        w
+    }
+
+    #[test]
+    fn fog_in_the_light_scatters_light_towards_the_eye() {
+        let w = sphere_under_a_light();
+        let r = Ray::new(
+            Tuple::new_point(5.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let c = w.integrate_volume(r, 2.0, Fog::new(0.1, 8));
+        let expected = 1.0 - f64::exp(-0.2);
+
+        assert!((c.red - expected).abs() < 0.001);
+        assert_eq!(c.red, c.green);
+        assert_eq!(c.red, c.blue);
+    }
+
+    #[test]
+    fn fog_in_a_shadow_stays_dark() {
+        let w = sphere_under_a_light();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -0.5),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+
+        assert_eq!(
+            w.integrate_volume(r, 1.0, Fog::new(0.1, 8)),
+            Color::new(0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn a_ray_crossing_a_shadow_picks_up_less_light() {
+        let w = sphere_under_a_light();
+        let lit = Ray::new(
+            Tuple::new_point(10.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let crossing = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let fog = Fog::new(0.05, 50);
+        let lit = w.integrate_volume(lit, 10.0, fog);
+        let crossing = w.integrate_volume(crossing, 10.0, fog);
+
+        assert!(crossing.red > 0.0);
+        assert!(crossing.red < lit.red);
+    }
+
+    fn shadow_edge_visibility(softness: f64) -> Vec<f64> {
+        let mut w = World::new();
+        w.light = Some(PointLight::new(
+            Tuple::new_point(0.0, 10.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let mut s = Sphere::new();
+        s.transform = Matrix4::translation(0.0, 2.0, 0.0);
+        w.objects.push(WorldShape::from(s));
+        w.objects.push(Plane::new().into());
+        w.contact_softness = softness;
+        (0..=20)
+            .map(|i| {
+                let x = 0.8 + i as f64 * 0.04;
+                let r = Ray::new(
+                    Tuple::new_point(x, 0.5, 0.0),
+                    Tuple::new_vector(0.0, -1.0, 0.0),
+                );
+                let xs = w.intersect_world(r);
+                let comps = xs.hit().unwrap().prepare_computations(r);
+                w.light_visibility(&comps)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shadows_without_contact_softness_have_a_hard_edge() {
+        let visibility = shadow_edge_visibility(0.0);
+
+        assert!(visibility.iter().all(|&v| v == 0.0 || v == 1.0));
+        assert_eq!(visibility[0], 0.0);
+        assert_eq!(visibility[20], 1.0);
+    }
+
+    #[test]
+    fn contact_softness_blurs_the_shadow_edge() {
+        let visibility = shadow_edge_visibility(0.3);
+        let penumbra = visibility.iter().filter(|&&v| 0.0 < v && v < 1.0).count();
+
+        assert!(penumbra > 2);
+        assert!(visibility.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(visibility[0], 0.0);
+        assert_eq!(visibility[20], 1.0);
+    }
+
+    #[test]
+    fn contact_softness_leaves_fully_lit_points_unchanged() {
+        let mut w = default_world();
+        w.contact_softness = 0.5;
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let hard = default_world().color_at(r);
+
+        assert_eq!(w.color_at(r), hard);
+    }
+
+    #[test]
+    fn path_tracing_inside_a_glowing_sphere_sums_the_bounces() {
+        let mut s = Sphere::new();
+        s.transform = Matrix4::scaling(10.0, 10.0, 10.0);
+        s.material.emissive = Color::new(0.5, 0.5, 0.5);
+        s.material.color = Color::new(1.0, 0.5, 0.0);
+        s.material.diffuse = 0.5;
+        let mut w = World::new();
+        w.objects.push(s);
+        let mut rng = XorShift::new(1);
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, 0.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let c = w.path_trace(r, &mut rng, 3);
+
+        assert_eq!(
+            c,
+            Color::new(
+                0.5 * (1.0 + 0.5 + 0.25 + 0.125),
+                0.5 * (1.0 + 0.25 + 0.0625 + 0.015625),
+                0.5
+            )
+        );
+    }
+
+    #[test]
+    fn path_tracing_a_floor_under_a_uniform_sky() {
+        let mut p = Plane::new();
+        p.material.diffuse = 0.8;
+        let mut w = World::new();
+        w.objects.push(p);
+        w.background = Background::Solid(Color::new(1.0, 1.0, 1.0));
+        let mut rng = XorShift::new(1);
+        let r = Ray::new(
+            Tuple::new_point(0.0, 1.0, -1.0),
+            Tuple::new_vector(0.0, -1.0, 1.0).normalize(),
+        );
+
+        for _ in 0..10 {
+            assert_eq!(w.path_trace(r, &mut rng, 4), Color::new(0.8, 0.8, 0.8));
+        }
+        assert_eq!(w.path_trace(r, &mut rng, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn path_tracing_a_patterned_floor_uses_the_pattern_color() {
+        let mut p = Plane::new();
+        p.material.diffuse = 0.8;
+        p.material.pattern = Some(Box::new(SolidPattern::new(Color::new(1.0, 0.5, 0.0))));
+        let mut w = World::new();
+        w.objects.push(p);
+        w.background = Background::Solid(Color::new(1.0, 1.0, 1.0));
+        let mut rng = XorShift::new(1);
+        let r = Ray::new(
+            Tuple::new_point(0.0, 1.0, -1.0),
+            Tuple::new_vector(0.0, -1.0, 1.0).normalize(),
+        );
+
+        assert_eq!(w.path_trace(r, &mut rng, 4), Color::new(0.8, 0.4, 0.0));
+    }
+
+    #[test]
+    fn russian_roulette_matches_a_fixed_bounce_count_on_average() {
+        let floor = Plane {
+            material: Material {
+                diffuse: 0.8,
+                ..Material::new()
+            },
+            ..Plane::new()
+        };
+        let ceiling = Plane {
+            transform: Matrix4::translation(0.0, 2.0, 0.0),
+            material: Material {
+                diffuse: 0.5,
+                emissive: Color::new(1.0, 1.0, 1.0),
+                ..Material::new()
+            },
+            ..Plane::new()
+        };
+        let mut w = World::new();
+        w.objects.push(floor);
+        w.objects.push(ceiling);
+        let r = Ray::new(
+            Tuple::new_point(0.0, 1.0, 0.0),
+            Tuple::new_vector(0.0, -1.0, 0.0),
+        );
+        let mut rng = XorShift::new(1);
+        let fixed = w.path_trace(r, &mut rng, 40);
+        let n = 20000;
+        let mut sum = 0.0;
+        for _ in 0..n {
+            let c = w.path_trace_with(
+                r,
+                &mut rng,
+                PathTermination::RussianRoulette { min_bounces: 0 },
+                None,
+            );
+            sum += c.red;
+        }
+
+        // Between the two planes the floor sees 0.8 / (1 - 0.8 * 0.5) of the ceiling's glow.
+        assert_float_eq!(fixed.red, 0.8 / 0.6);
+        assert!((sum / n as f64 - fixed.red).abs() < 0.03);
+    }
+
+    #[test]
+    fn next_event_estimation_sees_the_point_light() {
+        let mut floor = Plane::new();
+        floor.material.diffuse = 0.8;
+        let mut w = World::new();
+        w.objects.push(floor);
+        w.light = Some(PointLight::new(
+            Tuple::new_point(0.0, 4.0, -3.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let r = Ray::new(
+            Tuple::new_point(0.0, 1.0, -1.0),
+            Tuple::new_vector(0.0, -1.0, 1.0).normalize(),
+        );
+        let mut rng = XorShift::new(1);
+        let termination = PathTermination::MaxBounces(1);
+
+        let emitters = w.emitters();
+        // The light is 5 away, at a cosine of 0.8.
+        let direct = 0.8 * 0.8 / (PI * 25.0);
+
+        assert!(emitters.is_empty());
+        assert_eq!(
+            w.path_trace_with(r, &mut rng, termination, Some(&emitters)),
+            Color::new(direct, direct, direct)
+        );
+        assert_eq!(
+            w.path_trace_with(r, &mut rng, termination, None),
+            Color::new(0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn next_event_estimation_is_far_less_noisy_on_a_small_light() {
+        let mut floor = Plane::new();
+        floor.material.diffuse = 0.8;
+        let mut lamp = Sphere::new();
+        lamp.transform = Matrix4::scaling(0.2, 0.2, 0.2).translate(0.0, 2.0, 0.0);
+        lamp.material.emissive = Color::new(20.0, 20.0, 20.0);
+        let mut w: World<WorldShape> = World::new();
+        w.objects.push(floor.into());
+        w.objects.push(lamp.into());
+        let r = Ray::new(
+            Tuple::new_point(0.0, 1.0, -1.0),
+            Tuple::new_vector(0.0, -1.0, 1.0).normalize(),
+        );
+        let mut rng = XorShift::new(1);
+        let n = 4000;
+        let emitters = w.emitters();
+        let mut estimate = |next_event: bool| {
+            let emitters = next_event.then_some(&emitters[..]);
+            let samples: Vec<f64> = (0..n)
+                .map(|_| {
+                    w.path_trace_with(r, &mut rng, PathTermination::MaxBounces(1), emitters)
+                        .red
+                })
+                .collect();
+            let mean = samples.iter().sum::<f64>() / n as f64;
+            let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n as f64;
+            (mean, variance)
+        };
+        let (brute_mean, brute_variance) = estimate(false);
+        let (nee_mean, nee_variance) = estimate(true);
+
+        assert!(nee_mean > 0.0);
+        assert!((brute_mean - nee_mean).abs() < 0.25 * nee_mean);
+        assert!(nee_variance * 20.0 < brute_variance);
+    }
+
+    #[test]
+    fn next_event_estimation_finds_emitters_inside_groups_and_csg_shapes() {
+        let mut lamp = Sphere::new();
+        lamp.transform = Matrix4::scaling(0.5, 0.5, 0.5);
+        lamp.material.emissive = Color::new(5.0, 5.0, 5.0);
+        let mut group = Group::new();
+        group.transform = Matrix4::translation(0.0, 2.0, 0.0);
+        group.add_child(lamp.clone());
+        let mut bead = Sphere::new();
+        bead.transform = Matrix4::scaling(0.1, 0.1, 0.1);
+        let mut csg = Csg::new(Operation::Union, lamp, bead);
+        csg.transform = Matrix4::translation(0.0, 2.0, 0.0);
+        let r = Ray::new(
+            Tuple::new_point(0.0, 1.0, -1.0),
+            Tuple::new_vector(0.0, -1.0, 1.0).normalize(),
+        );
+
+        for holder in [WorldShape::from(group), WorldShape::from(csg)] {
+            let mut floor = Plane::new();
+            floor.material.diffuse = 0.8;
+            let mut w: World<WorldShape> = World::new();
+            w.objects.push(floor.into());
+            w.objects.push(holder);
+            let mut rng = XorShift::new(1);
+            let n = 4000;
+            let emitters = w.emitters();
+            let mut mean = |next_event: bool| {
+                let emitters = next_event.then_some(&emitters[..]);
+                (0..n)
+                    .map(|_| {
+                        w.path_trace_with(r, &mut rng, PathTermination::MaxBounces(1), emitters)
+                            .red
+                    })
+                    .sum::<f64>()
+                    / n as f64
+            };
+            let brute = mean(false);
+            let nee = mean(true);
+
+            assert!(nee > 0.0);
+            assert!((brute - nee).abs() < 0.25 * nee);
+        }
+    }
+
+    #[test]
+    fn validating_a_well_formed_world() {
+        let w = default_world();
+
+        assert!(w.validate().is_empty());
+    }
+
+    #[test]
+    fn validating_a_lightless_world_with_a_flattened_sphere() {
+        let mut s = Sphere::new();
+        s.transform = Matrix4::scaling(0.0, 0.0, 0.0);
+        let mut w = World::new();
+        w.objects.push(s);
+
+        assert_eq!(
+            w.validate(),
+            vec![SceneWarning::NoLight, SceneWarning::SingularTransform(0)]
+        );
+    }
+
+    #[test]
+    fn validating_a_world_with_an_invisible_material() {
+        let mut w = default_world();
+        w.objects[1].material.ambient = 0.0;
+        w.objects[1].material.diffuse = 0.0;
+        w.objects[1].material.specular = 0.0;
+
+        assert_eq!(w.validate(), vec![SceneWarning::ZeroMaterial(1)]);
+    }
+
+    #[test]
+    fn black_materials_that_reflect_glow_or_are_patterned_are_not_invisible() {
+        let mut w = default_world();
+        w.objects[1].material.color = Color::new(0.0, 0.0, 0.0);
+
+        assert_eq!(w.validate(), vec![SceneWarning::ZeroMaterial(1)]);
+
+        let mut mirror = w.clone();
+        mirror.objects[1].material.reflective = 1.0;
+        let mut glass = w.clone();
+        glass.objects[1].material.transparency = 1.0;
+        let mut lamp = w.clone();
+        lamp.objects[1].material.emissive = Color::new(1.0, 1.0, 1.0);
+        let mut patterned = w.clone();
+        patterned.objects[1].material.pattern = Some(Box::new(TestPattern::new()));
+
+        for w in [mirror, glass, lamp, patterned] {
+            assert!(w.validate().is_empty());
+        }
+    }
+
+    fn sphere_resting_on_a_floor() -> World {
+        let mut w = World::new();
+        w.light = Some(PointLight::new(
+            Tuple::new_point(-10.0, 10.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        w.objects.push(Plane::new().into());
+        let mut s = Sphere::new();
+        s.transform = Matrix4::translation(0.0, 1.0, 0.0);
+        w.objects.push(s.into());
+        w
+    }
+
+    #[test]
+    fn a_point_in_a_crevice_is_more_occluded_than_an_exposed_point() {
+        let w = sphere_resting_on_a_floor();
+        let floor = &w.objects[0];
+        let crevice = Ray::new(
+            Tuple::new_point(0.3, 5.0, 0.0),
+            Tuple::new_vector(0.0, -1.0, 0.0),
+        );
+        let exposed = Ray::new(
+            Tuple::new_point(10.0, 5.0, 0.0),
+            Tuple::new_vector(0.0, -1.0, 0.0),
+        );
+        let crevice_comps = Intersection::new(5.0, floor).prepare_computations(crevice);
+        let exposed_comps = Intersection::new(5.0, floor).prepare_computations(exposed);
+
+        let crevice_factor = w.ambient_occlusion(&crevice_comps, 32, 1.0);
+        let exposed_factor = w.ambient_occlusion(&exposed_comps, 32, 1.0);
+
+        assert_float_eq!(exposed_factor, 1.0);
+        assert!(crevice_factor < exposed_factor);
+    }
+
+    #[test]
+    fn shade_hit_darkens_the_ambient_term_when_occlusion_is_enabled() {
+        let mut w = sphere_resting_on_a_floor();
+        w.light = Some(PointLight::new(
+            Tuple::new_point(0.0, -10.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let r = Ray::new(
+            Tuple::new_point(0.3, 5.0, 0.0),
+            Tuple::new_vector(0.0, -1.0, 0.0),
+        );
+        let i = Intersection::new(5.0, &w.objects[0]);
+        let unoccluded = w.shade_hit(i.prepare_computations(r), RECURSION_DEPTH);
+        w.occlusion = Some(AmbientOcclusion::new(32, 1.0));
+        let occluded = w.shade_hit(i.prepare_computations(r), RECURSION_DEPTH);
+
+        assert_eq!(unoccluded, Color::new(0.1, 0.1, 0.1));
+        assert!(occluded.red < unoccluded.red);
+    }
 }